@@ -0,0 +1,324 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use rug::{ops::Pow, Float};
+use threadpool::ThreadPool;
+
+use crate::mandelbrot::{
+    bounded::BoundsSettings,
+    compute::{Compute, ComputeEngine, ComputeError, ComputeSettings, ComputedSet},
+};
+use crate::ui::{color::ColorSettings, gradient::builtin_gradients};
+
+/// A view on the complex plane, given by its center and vertical extent.
+pub struct View {
+    pub x: Float,
+    pub y: Float,
+    pub scale: Float,
+}
+
+impl View {
+    pub fn new(x: Float, y: Float, scale: Float) -> View {
+        View { x, y, scale }
+    }
+
+    /// Moves linearly towards `other` while zooming geometrically, so every frame
+    /// of an animation zooms in by the same factor.
+    fn interpolate(&self, other: &View, t: f64, precision: u32) -> View {
+        let lerp = |a: &Float, b: &Float| {
+            let delta = Float::with_val(precision, b - a);
+            Float::with_val(precision, a + delta * t)
+        };
+        let zoom = Float::with_val(precision, &other.scale / &self.scale);
+        View {
+            x: lerp(&self.x, &other.x),
+            y: lerp(&self.y, &other.y),
+            scale: Float::with_val(precision, &self.scale * zoom.pow(t)),
+        }
+    }
+}
+
+pub fn write_png(
+    path: &Path,
+    set: &ComputedSet,
+    color: &ColorSettings,
+    depth: png::BitDepth,
+) -> Result<(), png::EncodingError> {
+    let (width, height) = set.get_size();
+    let pixels = color
+        .colorize(set)
+        .unwrap_or_else(|| vec![0.0; width as usize * height as usize * 4]);
+    write_pixels(path, width, height, &pixels, depth, None)
+}
+
+/// Quantizes RGBA `pixels`, stored bottom row first like the textures, to the samples of an
+/// image, top row first, with 16 bits per channel for `BitDepth::Sixteen` and 8 otherwise.
+fn rgba_bytes(pixels: &[f32], width: u32, depth: png::BitDepth) -> Vec<u8> {
+    let mut data = Vec::with_capacity(pixels.len() * 2);
+    // textures are stored bottom row first, images top row first
+    for row in pixels.chunks(width as usize * 4).rev() {
+        for c in row {
+            let c = c.max(0.0).min(1.0);
+            match depth {
+                // 16 bit samples are stored big endian
+                png::BitDepth::Sixteen => {
+                    data.extend_from_slice(&((c * 65535.0).round() as u16).to_be_bytes())
+                }
+                _ => data.push((c * 255.0).round() as u8),
+            }
+        }
+    }
+    data
+}
+
+/// Writes RGBA `pixels`, stored bottom row first like the textures, as a PNG with 8 or,
+/// for `BitDepth::Sixteen`, 16 bits per channel, and with `dpi` as its print resolution.
+fn write_pixels(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[f32],
+    depth: png::BitDepth,
+    dpi: Option<u32>,
+) -> Result<(), png::EncodingError> {
+    let depth = match depth {
+        png::BitDepth::Sixteen => png::BitDepth::Sixteen,
+        _ => png::BitDepth::Eight,
+    };
+    let data = rgba_bytes(pixels, width, depth);
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(depth);
+    // PNG stores the resolution in pixels per meter
+    encoder.set_pixel_dims(dpi.map(|dpi| png::PixelDimensions {
+        xppu: (dpi as f64 / 0.0254).round() as u32,
+        yppu: (dpi as f64 / 0.0254).round() as u32,
+        unit: png::Unit::Meter,
+    }));
+    encoder.write_header()?.write_image_data(&data)
+}
+
+/// Copies the colored set to the clipboard as an 8 bit RGBA image.
+pub fn copy_image(set: &ComputedSet, color: &ColorSettings) -> Result<(), arboard::Error> {
+    let (width, height) = set.get_size();
+    let pixels = color
+        .colorize(set)
+        .unwrap_or_else(|| vec![0.0; width as usize * height as usize * 4]);
+    let bytes = rgba_bytes(&pixels, width, png::BitDepth::Eight);
+    arboard::Clipboard::new()?.set_image(arboard::ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: bytes.into(),
+    })
+}
+
+/// Writes one cycle of the palette of `color` from left to right as an 8 bit PNG strip.
+pub fn palette_swatch(
+    path: &Path,
+    color: &ColorSettings,
+    size: [u32; 2],
+) -> Result<(), png::EncodingError> {
+    let [width, height] = size;
+    // a count of n out of 360 iterations sits n degrees into the cycle
+    let row: Vec<f32> = color
+        .legend(360, 2.0, width as usize)
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+    let pixels = row.repeat(height as usize);
+    write_pixels(path, width, height, &pixels, png::BitDepth::Eight, None)
+}
+
+/// Averages every `factor` by `factor` block of the RGBA `pixels` into a single pixel.
+fn downsample(pixels: &[f32], width: u32, height: u32, factor: u32) -> Vec<f32> {
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let (out_width, out_height) = (width / factor, height / factor);
+    let weight = 1.0 / (factor * factor) as f32;
+    let mut out = vec![0.0; out_width * out_height * 4];
+    for y in 0..out_height * factor {
+        for x in 0..out_width * factor {
+            let source = (y * width + x) * 4;
+            let target = ((y / factor) * out_width + x / factor) * 4;
+            for c in 0..4 {
+                out[target + c] += pixels[source + c] * weight;
+            }
+        }
+    }
+    out
+}
+
+/// Everything besides the view that determines a rendered image.
+#[derive(Clone)]
+pub struct RenderSettings {
+    pub size: [u32; 2],
+    pub engine: ComputeEngine,
+    pub bounds: BoundsSettings,
+    pub color: ColorSettings,
+    /// Bits per channel of the written images, 8 or 16.
+    pub depth: png::BitDepth,
+    /// Print resolution stored in the written images, if any.
+    pub dpi: Option<u32>,
+}
+
+impl RenderSettings {
+    pub fn compute(
+        &self,
+        view: View,
+        thread_pool: &mut ThreadPool,
+    ) -> Result<ComputedSet, ComputeError> {
+        // the view keeps the precision it was placed with, the bounds may iterate with less
+        let precision = view.x.prec();
+        Compute::compute_set(
+            Some(thread_pool),
+            None,
+            &ComputeSettings::new(
+                view.x,
+                view.y,
+                view.scale,
+                self.size[0],
+                self.size[1],
+                self.engine,
+                self.bounds.with_periods(self.color.color_period),
+            )
+            .with_precision(precision),
+        )
+    }
+}
+
+/// Renders a single view to a PNG at `path` and returns the set it was colored from. A
+/// `supersample` factor above 1 computes the set at that many times the size along each axis
+/// and averages the colored blocks down.
+pub fn render_png(
+    view: View,
+    render: &RenderSettings,
+    supersample: u32,
+    path: &Path,
+) -> Result<ComputedSet, Box<dyn Error>> {
+    let factor = supersample.max(1);
+    let oversampled = RenderSettings {
+        size: [render.size[0] * factor, render.size[1] * factor],
+        ..render.clone()
+    };
+    let set = oversampled.compute(view, &mut ThreadPool::new(8))?;
+    let (width, height) = set.get_size();
+    let pixels = render
+        .color
+        .colorize(&set)
+        .unwrap_or_else(|| vec![0.0; width as usize * height as usize * 4]);
+    let pixels = if factor == 1 {
+        pixels
+    } else {
+        downsample(&pixels, width, height, factor)
+    };
+    write_pixels(
+        path,
+        render.size[0],
+        render.size[1],
+        &pixels,
+        render.depth,
+        render.dpi,
+    )?;
+    Ok(set)
+}
+
+/// Summary of a finished computation as a single line JSON object, for benchmark records.
+pub fn stats_json(
+    engine: ComputeEngine,
+    bounds: &BoundsSettings,
+    set: &ComputedSet,
+    elapsed: Duration,
+) -> String {
+    let (width, height) = set.get_size();
+    let stats = set.stats();
+    format!(
+        "{{\"engine\": \"{:?}\", \"width\": {}, \"height\": {}, \"iterations\": {}, \
+         \"precision\": {}, \"elapsed\": {}, \"bounded\": {}, \"unbounded\": {}}}",
+        engine,
+        width,
+        height,
+        bounds.limit,
+        bounds.precision,
+        elapsed.as_secs_f64(),
+        stats.bounded,
+        stats.unbounded
+    )
+}
+
+/// Renders `frames` images zooming from `start` to `end` into `out_dir` as numbered PNGs.
+pub fn zoom_sequence(
+    start: &View,
+    end: &View,
+    frames: u32,
+    render: &RenderSettings,
+    out_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut thread_pool = ThreadPool::new(8);
+    for frame in 0..frames {
+        let t = if frames > 1 {
+            frame as f64 / (frames - 1) as f64
+        } else {
+            0.0
+        };
+        let view = start.interpolate(end, t, render.bounds.precision);
+        let set = render.compute(view, &mut thread_pool)?;
+        let path = out_dir.join(format!("frame_{:05}.png", frame));
+        write_png(&path, &set, &render.color, render.depth)?;
+        println!("frame {}/{}: {}", frame + 1, frames, path.display());
+    }
+    Ok(())
+}
+
+/// Renders the queued `jobs` one after the other into `out_dir` as `job_<n>.png`.
+pub fn render_jobs(
+    jobs: Vec<(View, RenderSettings)>,
+    out_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut thread_pool = ThreadPool::new(8);
+    let count = jobs.len();
+    for (n, (view, render)) in jobs.into_iter().enumerate() {
+        println!("job {}/{}: computing", n + 1, count);
+        let start = std::time::Instant::now();
+        let set = render.compute(view, &mut thread_pool)?;
+        let path = out_dir.join(format!("job_{:03}.png", n + 1));
+        write_png(&path, &set, &render.color, render.depth)?;
+        println!(
+            "job {}/{}: {} in {:.2} seconds",
+            n + 1,
+            count,
+            path.display(),
+            start.elapsed().as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// Renders `view` once and writes it colored with the default and every built-in palette
+/// as `palette_<name>.png` into `out_dir`.
+pub fn palette_previews(
+    view: View,
+    render: &RenderSettings,
+    out_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let set = render.compute(view, &mut ThreadPool::new(8))?;
+    let mut palettes = vec![("hue", None)];
+    palettes.extend(
+        builtin_gradients()
+            .into_iter()
+            .map(|(name, gradient)| (name, Some(gradient))),
+    );
+    for (name, gradient) in palettes {
+        let color = ColorSettings {
+            gradient,
+            ..render.color.clone()
+        };
+        let path = out_dir.join(format!("palette_{}.png", name));
+        write_png(&path, &set, &color, render.depth)?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
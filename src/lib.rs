@@ -0,0 +1,7 @@
+//! Compute engine of the Mandelbrot explorer, usable without its UI.
+#![feature(test)]
+
+#[cfg(test)]
+extern crate test;
+
+pub mod mandelbrot;
@@ -1,27 +1,71 @@
-#![feature(test)]
-
-extern crate test;
-
 #[macro_use]
 extern crate imgui;
 #[macro_use]
 extern crate glium;
 extern crate imgui_glium_renderer;
 extern crate imgui_winit_support;
-extern crate packed_simd;
 extern crate palette;
 extern crate rug;
 extern crate threadpool;
 extern crate time;
 
-mod mandelbrot;
+mod export;
 mod ui;
 
+use mandelbrot_rust::mandelbrot;
 use ui::app::{App, AppSettings};
 
+fn open(settings: AppSettings) -> App {
+    App::new(settings).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Opens the explorer with the `--goto <location>`, `--no-vsync` and `--palette <file>`
+/// options, given in any order.
+fn launch<I: Iterator<Item = String>>(mut options: I) {
+    let mut settings = AppSettings::new();
+    let mut location = None;
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--goto" => {
+                location = Some(options.next().unwrap_or_else(|| {
+                    eprintln!("usage: --goto \"<location>\"");
+                    std::process::exit(1);
+                }));
+            }
+            "--no-vsync" => settings.vsync = false,
+            "--palette" => {
+                let path = options.next().unwrap_or_else(|| {
+                    eprintln!("usage: --palette <file>");
+                    std::process::exit(1);
+                });
+                match ui::gradient::load_gradient(std::path::Path::new(&path)) {
+                    Ok(gradient) => settings.color.gradient = Some(gradient),
+                    Err(e) => eprintln!("failed to load palette, using the default: {}", e),
+                }
+            }
+            _ => {
+                eprintln!("unknown option: {}", option);
+                std::process::exit(1);
+            }
+        }
+    }
+    let mut app = open(settings);
+    if let Some(location) = location {
+        if let Err(e) = app.goto(&location) {
+            eprintln!("invalid location: {}", e);
+            std::process::exit(1);
+        }
+    }
+    app.run();
+}
+
 fn main() {
     let mut args = std::env::args();
-    match args.nth(1).unwrap_or_else(|| String::from("")).as_str() {
+    let command = args.nth(1).unwrap_or_else(|| String::from(""));
+    match command.as_str() {
         "perf_test" => {
             use rug::Float;
 
@@ -30,29 +74,285 @@ fn main() {
                 compute::{Compute, ComputeEngine, ComputeSettings},
             };
 
+            let json = args.next().as_deref() == Some("--json");
+
             let start = std::time::Instant::now();
 
             let size = (1600 / 2, 900 / 2);
 
             let precision = 53;
+            let engine = ComputeEngine::Precision;
+            let bounds = BoundsSettings::new(250, precision);
             let settings = ComputeSettings::new(
                 Float::with_val(precision, -0.5),
                 Float::with_val(precision, 0.0),
                 Float::with_val(precision, 1.75),
                 size.0,
                 size.1,
-                ComputeEngine::Precision,
-                BoundsSettings::new(250, precision),
+                engine,
+                bounds,
             );
 
-            Compute::compute_set(None, None, &settings);
+            let set = Compute::compute_set(None, None, &settings).unwrap();
 
             let duration = std::time::Instant::now() - start;
-            println!("{}", duration.as_secs_f64());
+            if json {
+                println!("{}", export::stats_json(engine, &bounds, &set, duration));
+            } else {
+                println!("{}", duration.as_secs_f64());
+            }
+        }
+        "animate" => {
+            use rug::Float;
+
+            use export::{RenderSettings, View};
+            use mandelbrot::{bounded::BoundsSettings, compute::ComputeEngine};
+            use ui::color::ColorSettings;
+
+            let args: Vec<String> = args.collect();
+            if args.len() != 8 {
+                eprintln!(
+                    "usage: animate <x0> <y0> <scale0> <x1> <y1> <scale1> <frames> <out_dir>"
+                );
+                std::process::exit(1);
+            }
+
+            let precision = 53;
+            let float = |s: &String| match Float::parse(s) {
+                Ok(f) => Float::with_val(precision, f),
+                Err(e) => {
+                    eprintln!("invalid number '{}': {}", s, e);
+                    std::process::exit(1);
+                }
+            };
+            let start = View::new(float(&args[0]), float(&args[1]), float(&args[2]));
+            let end = View::new(float(&args[3]), float(&args[4]), float(&args[5]));
+            let frames = args[6].parse().unwrap_or_else(|e| {
+                eprintln!("invalid frame count '{}': {}", args[6], e);
+                std::process::exit(1);
+            });
+
+            let render = RenderSettings {
+                size: [1600, 900],
+                engine: ComputeEngine::SimdF64x4,
+                bounds: BoundsSettings::new(1000, precision),
+                color: ColorSettings::new(),
+                depth: png::BitDepth::Eight,
+                dpi: None,
+            };
+            if let Err(e) = export::zoom_sequence(
+                &start,
+                &end,
+                frames,
+                &render,
+                std::path::Path::new(&args[7]),
+            ) {
+                eprintln!("failed to render animation: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "render" => {
+            use rug::Float;
+
+            use export::{RenderSettings, View};
+            use mandelbrot::{
+                bounded::{BoundsSettings, Fractal},
+                compute::ComputeEngine,
+            };
+            use ui::color::ColorSettings;
+
+            fn fail(message: String) -> ! {
+                eprintln!("{}", message);
+                eprintln!(
+                    "usage: render --x <x> --y <y> --scale <scale> [--width <px>] \
+                     [--height <px>] [--iterations <n>] [--precision <bits>] [--engine <name>] \
+                     [--fractal <name>] [--bailout <radius>] [--supersample <n>] \
+                     [--depth <8|16>] [--dpi <n> [--print-width <in> --print-height <in>]] \
+                     [--json <file.json>] --out <file.png>"
+                );
+                std::process::exit(1);
+            }
+
+            let args: Vec<String> = args.collect();
+            let mut options = std::collections::HashMap::new();
+            for pair in args.chunks(2) {
+                match pair {
+                    [key, value] if key.starts_with("--") => {
+                        options.insert(&key[2..], value.as_str());
+                    }
+                    _ => fail(format!("expected --option value pairs, got '{}'", pair[0])),
+                }
+            }
+            let option = |key: &str| {
+                *options
+                    .get(key)
+                    .unwrap_or_else(|| fail(format!("missing --{}", key)))
+            };
+            let number = |key: &str, default: f64| match options.get(key) {
+                Some(value) => value
+                    .parse::<f64>()
+                    .unwrap_or_else(|e| fail(format!("invalid --{} '{}': {}", key, value, e))),
+                None => default,
+            };
+
+            let precision = number("precision", 53.0) as u32;
+            let float = |key: &str| match Float::parse(option(key)) {
+                Ok(f) => Float::with_val(precision, f),
+                Err(e) => fail(format!("invalid --{}: {}", key, e)),
+            };
+            let view = View::new(float("x"), float("y"), float("scale"));
+            let engine = match options.get("engine") {
+                Some(name) => ComputeEngine::from_name(name)
+                    .unwrap_or_else(|| fail(format!("unknown engine '{}'", name))),
+                None => ComputeEngine::SimdF64x4,
+            };
+            let fractal = match options.get("fractal") {
+                Some(name) => Fractal::from_name(name)
+                    .unwrap_or_else(|| fail(format!("unknown fractal '{}'", name))),
+                None => Fractal::Mandelbrot,
+            };
+
+            // a print size in inches at the given DPI overrides the pixel size
+            let dpi = options.get("dpi").map(|_| number("dpi", 0.0));
+            let size = match (dpi, options.get("print-width"), options.get("print-height")) {
+                (Some(dpi), Some(_), Some(_)) => [
+                    (number("print-width", 0.0) * dpi).round() as u32,
+                    (number("print-height", 0.0) * dpi).round() as u32,
+                ],
+                (_, None, None) => [
+                    number("width", 1600.0) as u32,
+                    number("height", 900.0) as u32,
+                ],
+                _ => fail(String::from(
+                    "--print-width and --print-height go together, with --dpi",
+                )),
+            };
+            let render = RenderSettings {
+                size,
+                engine,
+                bounds: BoundsSettings::new(number("iterations", 1000.0) as u64, precision)
+                    .with_fractal(fractal)
+                    .with_bailout(number("bailout", 2.0)),
+                color: ColorSettings::new(),
+                depth: match number("depth", 8.0) as u32 {
+                    8 => png::BitDepth::Eight,
+                    16 => png::BitDepth::Sixteen,
+                    depth => fail(format!("unsupported --depth {}, use 8 or 16", depth)),
+                },
+                dpi: dpi.map(|dpi| dpi.round() as u32),
+            };
+            let supersample = number("supersample", 1.0) as u32;
+            let out = std::path::Path::new(option("out"));
+            let start = std::time::Instant::now();
+            let set = export::render_png(view, &render, supersample, out).unwrap_or_else(|e| {
+                eprintln!("failed to render: {}", e);
+                std::process::exit(1);
+            });
+            if let Some(path) = options.get("json") {
+                let json = export::stats_json(render.engine, &render.bounds, &set, start.elapsed());
+                if let Err(e) = std::fs::write(path, json + "\n") {
+                    eprintln!("failed to write {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "palettes" => {
+            use rug::Float;
+
+            use export::{RenderSettings, View};
+            use mandelbrot::{bounded::BoundsSettings, compute::ComputeEngine};
+            use ui::color::ColorSettings;
+
+            let out_dir = args.next().unwrap_or_else(|| {
+                eprintln!("usage: palettes <out_dir>");
+                std::process::exit(1);
+            });
+
+            let precision = 53;
+            let view = View::new(
+                Float::with_val(precision, -0.5),
+                Float::with_val(precision, 0.0),
+                Float::with_val(precision, 1.75),
+            );
+            let render = RenderSettings {
+                size: [800, 450],
+                engine: ComputeEngine::SimdF64x4,
+                bounds: BoundsSettings::new(1000, precision),
+                color: ColorSettings::new(),
+                depth: png::BitDepth::Eight,
+                dpi: None,
+            };
+            if let Err(e) = export::palette_previews(view, &render, std::path::Path::new(&out_dir))
+            {
+                eprintln!("failed to render palette previews: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "batch" => {
+            use ui::app::ZoomState;
+
+            let (jobs_file, out_dir) = match (args.next(), args.next()) {
+                (Some(jobs_file), Some(out_dir)) => (jobs_file, out_dir),
+                _ => {
+                    eprintln!("usage: batch <jobs_file> <out_dir>");
+                    std::process::exit(1);
+                }
+            };
+            let jobs = std::fs::read_to_string(&jobs_file).unwrap_or_else(|e| {
+                eprintln!("failed to read {}: {}", jobs_file, e);
+                std::process::exit(1);
+            });
+            let defaults = AppSettings::new();
+            let mut queue = Vec::new();
+            for (n, line) in jobs.lines().enumerate() {
+                if line.trim().is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                match ZoomState::parse_location(line, &defaults) {
+                    Ok((zoom, settings)) => queue.push(zoom.render_job(&settings)),
+                    Err(e) => {
+                        eprintln!("{}:{}: {}", jobs_file, n + 1, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if let Err(e) = export::render_jobs(queue, std::path::Path::new(&out_dir)) {
+                eprintln!("failed to render jobs: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "validate-palette" => {
+            use ui::color::ColorSettings;
+
+            let (path, swatch) = match (args.next(), args.next()) {
+                (Some(path), swatch) => (path, swatch),
+                _ => {
+                    eprintln!("usage: validate-palette <file> [<swatch.png>]");
+                    std::process::exit(1);
+                }
+            };
+            let gradient =
+                ui::gradient::load_gradient(std::path::Path::new(&path)).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+            println!("{}: {} stops", path, gradient.stops());
+            if let Some(swatch) = swatch {
+                let color = ColorSettings {
+                    gradient: Some(gradient),
+                    ..ColorSettings::new()
+                };
+                let swatch = std::path::Path::new(&swatch);
+                if let Err(e) = export::palette_swatch(swatch, &color, [512, 64]) {
+                    eprintln!("failed to write {}: {}", swatch.display(), e);
+                    std::process::exit(1);
+                }
+            }
         }
         _ => {
-            let app = App::new(AppSettings::new());
-            app.run();
+            // the options of the explorer itself, the first one was taken by the match
+            let options = std::iter::once(command).filter(|option| !option.is_empty());
+            launch(options.chain(args));
         }
     }
 }
@@ -0,0 +1,135 @@
+//! `cargo bench` timings of every `BoundsChecker`, for a single point deep inside the set,
+//! one that escapes quickly and a row crossing the set, and of every engine computing a whole
+//! row of a set including placing its points.
+
+use packed_simd::{f32x16, f32x8, f64x4};
+use rug::{Complex, Float};
+use test::Bencher;
+
+use crate::mandelbrot::bounded::{Bound, BoundsChecker, BoundsSettings, Orbit};
+use crate::mandelbrot::compute::{Compute, ComputeEngine, ComputeSettings};
+use crate::mandelbrot::double_double::DoubleDouble;
+
+const LIMIT: u64 = 1000;
+const INTERIOR: [f64; 2] = [-0.1, 0.1];
+const EXTERIOR: [f64; 2] = [0.5, 0.5];
+/// Points per row, a multiple of every lane count.
+const ROW: usize = 64;
+/// Pixels per row of a computed set, as wide as a typical window.
+const SET_ROW: u32 = 1600;
+
+fn row_x(i: usize) -> f64 {
+    -2.0 + 2.5 * i as f64 / ROW as f64
+}
+
+fn point<T: BoundsChecker<f64>>(b: &mut Bencher, c: [f64; 2]) {
+    let settings = BoundsSettings::new(LIMIT, 53);
+    let lanes = T::mask().len();
+    let (x, y) = (vec![c[0]; lanes], vec![c[1]; lanes]);
+    let mut out = vec![Bound::Bounded(Orbit::default()); lanes];
+    b.iter(|| T::check_bounded(&x, &y, &settings, &mut out));
+}
+
+fn row<T: BoundsChecker<f64>>(b: &mut Bencher) {
+    let settings = BoundsSettings::new(LIMIT, 53);
+    let x: Vec<f64> = (0..ROW).map(row_x).collect();
+    let y = vec![0.1; ROW];
+    let mut out = vec![Bound::Bounded(Orbit::default()); ROW];
+    let lanes = T::mask().len();
+    b.iter(|| {
+        for i in (0..ROW).step_by(lanes) {
+            let range = i..i + lanes;
+            T::check_bounded(
+                &x[range.clone()],
+                &y[range.clone()],
+                &settings,
+                &mut out[range],
+            );
+        }
+    });
+}
+
+fn point_hp<T: BoundsChecker<Float>>(b: &mut Bencher, c: [f64; 2]) {
+    let settings = BoundsSettings::new(LIMIT, 128);
+    let (x, y) = (
+        vec![Float::with_val(128, c[0])],
+        vec![Float::with_val(128, c[1])],
+    );
+    let mut out = vec![Bound::Bounded(Orbit::default())];
+    b.iter(|| T::check_bounded(&x, &y, &settings, &mut out));
+}
+
+fn row_hp<T: BoundsChecker<Float>>(b: &mut Bencher) {
+    let settings = BoundsSettings::new(LIMIT, 128);
+    let x: Vec<Float> = (0..ROW).map(|i| Float::with_val(128, row_x(i))).collect();
+    let y = vec![Float::with_val(128, 0.1)];
+    let mut out = vec![Bound::Bounded(Orbit::default())];
+    b.iter(|| {
+        for x in &x {
+            T::check_bounded(std::slice::from_ref(x), &y, &settings, &mut out);
+        }
+    });
+}
+
+/// A row above the set, where most points escape within a few iterations so the time spent
+/// placing them shows.
+fn set_row(b: &mut Bencher, engine: ComputeEngine) {
+    let settings = ComputeSettings::new(
+        Float::with_val(53, -0.75),
+        Float::with_val(53, 0.75),
+        Float::with_val(53, 2.5 / SET_ROW as f64),
+        SET_ROW,
+        1,
+        engine,
+        BoundsSettings::new(LIMIT, 53),
+    );
+    b.iter(|| Compute::compute_set(None, None, &settings).unwrap());
+}
+
+macro_rules! benches {
+    ($name:ident, $checker:ty, $engine:expr, $point:ident, $row:ident) => {
+        mod $name {
+            use super::*;
+
+            #[bench]
+            fn interior(b: &mut Bencher) {
+                $point::<$checker>(b, INTERIOR);
+            }
+
+            #[bench]
+            fn exterior(b: &mut Bencher) {
+                $point::<$checker>(b, EXTERIOR);
+            }
+
+            #[bench]
+            fn full_row(b: &mut Bencher) {
+                $row::<$checker>(b);
+            }
+
+            #[bench]
+            fn computed_row(b: &mut Bencher) {
+                set_row(b, $engine);
+            }
+        }
+    };
+}
+
+benches!(single, f32, ComputeEngine::Single, point, row);
+benches!(double, f64, ComputeEngine::Double, point, row);
+benches!(simd_f32x8, f32x8, ComputeEngine::SimdF32x8, point, row);
+benches!(simd_f32x16, f32x16, ComputeEngine::SimdF32x16, point, row);
+benches!(simd_f64x4, f64x4, ComputeEngine::SimdF64x4, point, row);
+benches!(
+    double_double,
+    DoubleDouble,
+    ComputeEngine::DoubleDouble,
+    point_hp,
+    row_hp
+);
+benches!(
+    precision,
+    Complex,
+    ComputeEngine::Precision,
+    point_hp,
+    row_hp
+);
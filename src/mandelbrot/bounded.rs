@@ -1,27 +1,191 @@
-use packed_simd::{f32x8, f64x4, u32x8, u64x4};
-use rug::{Assign, Complex, Float};
+use packed_simd::{f32x16, f32x8, f64x4, m32x16, m32x8, m64x4, u32x16, u32x8, u64x4};
+use rug::{ops::NegAssign, Assign, Complex, Float};
+
+use num_derive::{FromPrimitive, ToPrimitive};
+
+use crate::mandelbrot::double_double::DoubleDouble;
 
 #[derive(Debug, Copy, Clone)]
 pub enum Bound {
-    Bounded,
-    Unbounded(u64),
+    Bounded(Orbit),
+    Unbounded(u64, Orbit),
+}
+
+impl Bound {
+    pub fn orbit(&self) -> &Orbit {
+        match self {
+            Bound::Bounded(orbit) | Bound::Unbounded(_, orbit) => orbit,
+        }
+    }
+}
+
+/// State of an orbit at the point the iteration stopped.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Orbit {
+    pub z: [f64; 2],
+    /// Period of the attracting cycle the orbit was found to be caught in, 0 when unknown.
+    pub period: u32,
+    /// Average of `(1 + sin(density * arg(z))) / 2` over the iterations, and the same average
+    /// without the last one. Only the scalar engines fill these in, with a stripe density set.
+    pub stripe: f64,
+    pub stripe_previous: f64,
+    /// Derivative of `z` with respect to the point iterated, for the distance estimate. Only
+    /// the scalar engines fill it in, for the Mandelbrot set with `distance` set.
+    pub dz: [f64; 2],
 }
 
+impl Orbit {
+    /// Estimated distance from the point iterated to the set, `2 |z| ln|z| / |dz|`, or `None`
+    /// when the derivative was not computed.
+    pub fn distance(&self) -> Option<f64> {
+        let norm_z = self.z[0].hypot(self.z[1]);
+        let norm_dz = self.dz[0].hypot(self.dz[1]);
+        if norm_dz > 0.0 {
+            Some(2.0 * norm_z * norm_z.ln() / norm_dz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Squared distance below which an orbit is considered to have returned to an earlier point.
+const PERIOD_EPSILON2: f64 = 1e-20;
+
 pub trait BoundsChecker<F>: Send {
+    /// Iterates from `settings.start` up to `settings.limit`. When resuming (`start > 0`) the
+    /// iteration continues from the orbits already in `out`, otherwise it starts at zero.
     fn check_bounded(x: &[F], y: &[F], settings: &BoundsSettings, out: &mut [Bound]);
     fn mask() -> Vec<usize>;
 }
 
+/// Iterated formula, the variants take absolute values of parts of `z^2`.
+#[derive(Clone, Copy, Debug, PartialEq, FromPrimitive, ToPrimitive)]
+pub enum Fractal {
+    Mandelbrot,
+    BurningShip,
+    Celtic,
+    Perpendicular,
+}
+
+impl Fractal {
+    pub const LIST: [Self; 4] = [
+        Self::Mandelbrot,
+        Self::BurningShip,
+        Self::Celtic,
+        Self::Perpendicular,
+    ];
+
+    /// Looks up a fractal by its `Debug` name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::LIST
+            .iter()
+            .find(|fractal| format!("{:?}", fractal) == name)
+            .copied()
+    }
+}
+
+/// One iteration of `$fractal` for anything with float arithmetic and `abs`.
+macro_rules! fractal_step {
+    ($fractal:expr, $z:expr, $c:expr, $two:expr) => {{
+        let (x, y) = $z;
+        match $fractal {
+            Fractal::Mandelbrot => (x * x - y * y + $c.0, $two * x * y + $c.1),
+            Fractal::BurningShip => (x * x - y * y + $c.0, $two * (x * y).abs() + $c.1),
+            Fractal::Celtic => ((x * x - y * y).abs() + $c.0, $two * x * y + $c.1),
+            Fractal::Perpendicular => (x * x - y * y + $c.0, $c.1 - $two * x.abs() * y),
+        }
+    }};
+}
+
 #[derive(Copy, Clone)]
 pub struct BoundsSettings {
     pub limit: u64,
     pub precision: u32,
+    pub fractal: Fractal,
+    pub start: u64,
+    /// Escape radius, an orbit escapes once `|z| >= bailout`.
+    pub bailout: f64,
+    /// Frequency of the stripe average over the argument of `z`, 0 to skip computing it.
+    pub stripe_density: f64,
+    /// Tracks the derivative of the orbit for the distance estimate.
+    pub distance: bool,
+    /// Looks for the attracting cycle of bounded orbits, stopping early once it is found.
+    pub periods: bool,
 }
 
 impl BoundsSettings {
     pub fn new(limit: u64, precision: u32) -> BoundsSettings {
-        BoundsSettings { limit, precision }
+        BoundsSettings {
+            limit,
+            precision,
+            fractal: Fractal::Mandelbrot,
+            start: 0,
+            bailout: 2.0,
+            stripe_density: 0.0,
+            distance: false,
+            periods: false,
+        }
+    }
+
+    pub fn with_fractal(mut self, fractal: Fractal) -> BoundsSettings {
+        self.fractal = fractal;
+        self
+    }
+
+    pub fn with_bailout(mut self, bailout: f64) -> BoundsSettings {
+        self.bailout = bailout;
+        self
     }
+
+    pub fn with_stripe_density(mut self, stripe_density: f64) -> BoundsSettings {
+        self.stripe_density = stripe_density;
+        self
+    }
+
+    pub fn with_distance(mut self, distance: bool) -> BoundsSettings {
+        self.distance = distance;
+        self
+    }
+
+    pub fn with_periods(mut self, periods: bool) -> BoundsSettings {
+        self.periods = periods;
+        self
+    }
+
+    fn radius2(&self) -> f64 {
+        self.bailout * self.bailout
+    }
+}
+
+/// Period of the attracting cycle when `c` lies in the main cardioid (1) or the period 2
+/// bulb (2) of the Mandelbrot set, where every orbit is known to stay bounded.
+pub fn main_body_period(c: [f64; 2]) -> Option<u32> {
+    let [x, y] = c;
+    let y2 = y * y;
+    let q = (x - 0.25) * (x - 0.25) + y2;
+    if q * (q + (x - 0.25)) <= 0.25 * y2 {
+        Some(1)
+    } else if (x + 1.0) * (x + 1.0) + y2 <= 0.0625 {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// The first `max_points` values of `z` in the orbit of `c`, up to and including the one that
+/// escapes.
+pub fn orbit_path(c: [f64; 2], settings: &BoundsSettings, max_points: usize) -> Vec<[f64; 2]> {
+    let c = (c[0], c[1]);
+    let mut z: (f64, f64) = (0.0, 0.0);
+    let mut path = vec![[z.0, z.1]];
+    for _ in 0..settings.limit.min(max_points as u64) {
+        z = fractal_step!(settings.fractal, z, c, 2.0);
+        path.push([z.0, z.1]);
+        if z.0 * z.0 + z.1 * z.1 >= settings.radius2() {
+            break;
+        }
+    }
+    path
 }
 
 macro_rules! impl_boundscheck_primitive {
@@ -31,18 +195,81 @@ macro_rules! impl_boundscheck_primitive {
                 let x = x[0];
                 let y = y[0];
                 let c = (x, y);
-                let mut z = (0.0, 0.0);
-                let mut iter = 0;
+                let mut z: (f64, f64) = if settings.start > 0 {
+                    let z = out[0].orbit().z;
+                    (z[0], z[1])
+                } else {
+                    (0.0, 0.0)
+                };
+                let mut iter = settings.start;
+                // a resumed orbit picks its stripe sum back up from the average so far
+                let mut stripe = out[0].orbit().stripe * settings.start as f64;
+                let mut stripe_last = 0.0;
+                let distance = settings.distance && settings.fractal == Fractal::Mandelbrot;
+                let mut dz = if settings.start > 0 {
+                    out[0].orbit().dz
+                } else {
+                    [0.0, 0.0]
+                };
+                // Brent's cycle detection, compare against a reference point that is moved
+                // ahead to the current one after every doubling window
+                let mut reference = z;
+                let mut window = 1;
+                let mut steps = 0;
                 while iter < settings.limit {
-                    z = (z.0 * z.0 - z.1 * z.1 + c.0, 2.0 * z.0 * z.1 + c.1);
-                    if z.0 * z.0 + z.1 * z.1 < 4.0 {
+                    if distance {
+                        // dz' = 2 z dz + 1, from z before the step
+                        dz = [
+                            2.0 * (z.0 * dz[0] - z.1 * dz[1]) + 1.0,
+                            2.0 * (z.0 * dz[1] + z.1 * dz[0]),
+                        ];
+                    }
+                    z = fractal_step!(settings.fractal, z, c, 2.0);
+                    if z.0 * z.0 + z.1 * z.1 < settings.radius2() {
                         iter += 1;
+                        if settings.stripe_density > 0.0 {
+                            stripe_last =
+                                0.5 + 0.5 * (settings.stripe_density * z.1.atan2(z.0)).sin();
+                            stripe += stripe_last;
+                        }
                     } else {
-                        out[0] = Bound::Unbounded(iter);
+                        let orbit = Orbit {
+                            z: [z.0, z.1],
+                            stripe: stripe / iter.max(1) as f64,
+                            stripe_previous: (stripe - stripe_last) / (iter.max(2) - 1) as f64,
+                            dz,
+                            ..Orbit::default()
+                        };
+                        out[0] = Bound::Unbounded(iter, orbit);
                         return;
                     }
+                    if !settings.periods {
+                        continue;
+                    }
+                    steps += 1;
+                    let (dx, dy) = (z.0 - reference.0, z.1 - reference.1);
+                    if dx * dx + dy * dy < PERIOD_EPSILON2 {
+                        out[0] = Bound::Bounded(Orbit {
+                            z: [z.0, z.1],
+                            period: steps,
+                            stripe: stripe / iter.max(1) as f64,
+                            dz,
+                            ..Orbit::default()
+                        });
+                        return;
+                    }
+                    if steps == window {
+                        reference = z;
+                        window *= 2;
+                        steps = 0;
+                    }
                 }
-                out[0] = Bound::Bounded;
+                out[0] = Bound::Bounded(Orbit {
+                    z: [z.0, z.1],
+                    stripe: stripe / iter.max(1) as f64,
+                    dz,
+                    ..Orbit::default()
+                });
             }
 
             fn mask() -> Vec<usize> {
@@ -57,22 +284,91 @@ impl_boundscheck_primitive!(f32);
 
 impl BoundsChecker<Float> for Complex {
     fn check_bounded(x: &[Float], y: &[Float], settings: &BoundsSettings, out: &mut [Bound]) {
+        // never resumes, an orbit stored as f64 has lost the precision this engine is for
         let mut buffer = Complex::new(settings.precision);
+        let radius2 = settings.radius2();
         let c = Complex::with_val(settings.precision, (&x[0], &y[0]));
         let mut z = Complex::with_val(settings.precision, (0.0, 0.0));
         let mut iter = 0;
         while iter < settings.limit {
-            let z_temp = Complex::with_val(settings.precision, z.square_ref());
-            z.assign(z_temp + &c);
+            match settings.fractal {
+                Fractal::Mandelbrot => z.square_mut(),
+                Fractal::BurningShip => {
+                    z.mut_real().abs_mut();
+                    z.mut_imag().abs_mut();
+                    z.square_mut();
+                }
+                Fractal::Celtic => {
+                    z.square_mut();
+                    z.mut_real().abs_mut();
+                }
+                Fractal::Perpendicular => {
+                    z.mut_real().abs_mut();
+                    z.square_mut();
+                    z.mut_imag().neg_assign();
+                }
+            }
+            z += &c;
             buffer.assign(z.norm_ref());
-            if buffer.real() < &4 {
+            if *buffer.real() < radius2 {
                 iter += 1;
             } else {
-                out[0] = Bound::Unbounded(iter);
+                // an escaped z lies just outside the bailout, well within f64 range, so the
+                // smooth count taken from the rounded value matches the f64 engines
+                let z = [z.real().to_f64(), z.imag().to_f64()];
+                out[0] = Bound::Unbounded(
+                    iter,
+                    Orbit {
+                        z,
+                        ..Orbit::default()
+                    },
+                );
                 return;
             }
         }
-        out[0] = Bound::Bounded;
+        let z = [z.real().to_f64(), z.imag().to_f64()];
+        out[0] = Bound::Bounded(Orbit {
+            z,
+            ..Orbit::default()
+        });
+    }
+
+    fn mask() -> Vec<usize> {
+        vec![0]
+    }
+}
+
+impl BoundsChecker<Float> for DoubleDouble {
+    fn check_bounded(x: &[Float], y: &[Float], settings: &BoundsSettings, out: &mut [Bound]) {
+        // never resumes, for the same reason as the Complex engine
+        let c = (
+            DoubleDouble::from_float(&x[0]),
+            DoubleDouble::from_float(&y[0]),
+        );
+        let two = DoubleDouble::from(2.0);
+        let mut z = (DoubleDouble::default(), DoubleDouble::default());
+        let mut iter = 0;
+        while iter < settings.limit {
+            z = fractal_step!(settings.fractal, z, c, two);
+            if (z.0 * z.0 + z.1 * z.1).to_f64() < settings.radius2() {
+                iter += 1;
+            } else {
+                let z = [z.0.to_f64(), z.1.to_f64()];
+                out[0] = Bound::Unbounded(
+                    iter,
+                    Orbit {
+                        z,
+                        ..Orbit::default()
+                    },
+                );
+                return;
+            }
+        }
+        let z = [z.0.to_f64(), z.1.to_f64()];
+        out[0] = Bound::Bounded(Orbit {
+            z,
+            ..Orbit::default()
+        });
     }
 
     fn mask() -> Vec<usize> {
@@ -95,29 +391,47 @@ impl BoundsChecker<f64> for f32x8 {
         let y = f32x8::from_slice_aligned(&t);
         let c = (x, y);
         let mut z = (f32x8::splat(0.0), f32x8::splat(0.0));
-        let mut iter = u32x8::splat(0);
-
-        let check = f32x8::splat(4.0);
-        for _ in 0..settings.limit {
+        if settings.start > 0 {
+            let (mut zx, mut zy) = ([0f32; 8], [0f32; 8]);
+            for ((zx, zy), o) in zx.iter_mut().zip(zy.iter_mut()).zip(out.iter()) {
+                *zx = o.orbit().z[0] as f32;
+                *zy = o.orbit().z[1] as f32;
+            }
             z = (
-                z.0 * z.0 - z.1 * z.1 + c.0,
-                f32x8::splat(2.0) * z.0 * z.1 + c.1,
+                f32x8::from_slice_unaligned(&zx),
+                f32x8::from_slice_unaligned(&zy),
             );
-            let mask = (z.0 * z.0 + z.1 * z.1).lt(check);
-            if mask.none() {
+        }
+        let mut iter = u32x8::splat(settings.start as u32);
+        let mut active = m32x8::splat(true);
+
+        let check = f32x8::splat(settings.radius2() as f32);
+        for _ in settings.start..settings.limit {
+            // escaped lanes keep the value they escaped with
+            let next = fractal_step!(settings.fractal, z, c, f32x8::splat(2.0));
+            z = (active.select(next.0, z.0), active.select(next.1, z.1));
+            active &= (z.0 * z.0 + z.1 * z.1).lt(check);
+            if active.none() {
                 break;
             }
-            iter = mask.select(iter + u32x8::splat(1), iter);
+            iter = active.select(iter + u32x8::splat(1), iter);
         }
         let mut checks = [0; 8];
         iter.write_to_slice_aligned(&mut checks);
+        let (mut zx, mut zy) = ([0f32; 8], [0f32; 8]);
+        z.0.write_to_slice_unaligned(&mut zx);
+        z.1.write_to_slice_unaligned(&mut zy);
         out.iter_mut()
-            .zip(checks.iter())
-            .map(|(o, n)| {
+            .zip(checks.iter().zip(zx.iter().zip(zy.iter())))
+            .map(|(o, (n, (zx, zy)))| {
+                let orbit = Orbit {
+                    z: [*zx as f64, *zy as f64],
+                    ..Orbit::default()
+                };
                 *o = if *n < settings.limit as u32 {
-                    Bound::Unbounded(*n as u64)
+                    Bound::Unbounded(*n as u64, orbit)
                 } else {
-                    Bound::Bounded
+                    Bound::Bounded(orbit)
                 }
             })
             .for_each(drop);
@@ -128,6 +442,73 @@ impl BoundsChecker<f64> for f32x8 {
     }
 }
 
+impl BoundsChecker<f64> for f32x16 {
+    // 64 byte alignment isn't guaranteed for stack arrays, so every load is unaligned
+    fn check_bounded(x: &[f64], y: &[f64], settings: &BoundsSettings, out: &mut [Bound]) {
+        let mut t = [0f32; 16];
+        t.iter_mut()
+            .zip(x.iter())
+            .map(|(t, s)| *t = *s as f32)
+            .for_each(drop);
+        let x = f32x16::from_slice_unaligned(&t);
+        t.iter_mut()
+            .zip(y.iter())
+            .map(|(t, s)| *t = *s as f32)
+            .for_each(drop);
+        let y = f32x16::from_slice_unaligned(&t);
+        let c = (x, y);
+        let mut z = (f32x16::splat(0.0), f32x16::splat(0.0));
+        if settings.start > 0 {
+            let (mut zx, mut zy) = ([0f32; 16], [0f32; 16]);
+            for ((zx, zy), o) in zx.iter_mut().zip(zy.iter_mut()).zip(out.iter()) {
+                *zx = o.orbit().z[0] as f32;
+                *zy = o.orbit().z[1] as f32;
+            }
+            z = (
+                f32x16::from_slice_unaligned(&zx),
+                f32x16::from_slice_unaligned(&zy),
+            );
+        }
+        let mut iter = u32x16::splat(settings.start as u32);
+        let mut active = m32x16::splat(true);
+
+        let check = f32x16::splat(settings.radius2() as f32);
+        for _ in settings.start..settings.limit {
+            // escaped lanes keep the value they escaped with
+            let next = fractal_step!(settings.fractal, z, c, f32x16::splat(2.0));
+            z = (active.select(next.0, z.0), active.select(next.1, z.1));
+            active &= (z.0 * z.0 + z.1 * z.1).lt(check);
+            if active.none() {
+                break;
+            }
+            iter = active.select(iter + u32x16::splat(1), iter);
+        }
+        let mut checks = [0; 16];
+        iter.write_to_slice_unaligned(&mut checks);
+        let (mut zx, mut zy) = ([0f32; 16], [0f32; 16]);
+        z.0.write_to_slice_unaligned(&mut zx);
+        z.1.write_to_slice_unaligned(&mut zy);
+        out.iter_mut()
+            .zip(checks.iter().zip(zx.iter().zip(zy.iter())))
+            .map(|(o, (n, (zx, zy)))| {
+                let orbit = Orbit {
+                    z: [*zx as f64, *zy as f64],
+                    ..Orbit::default()
+                };
+                *o = if *n < settings.limit as u32 {
+                    Bound::Unbounded(*n as u64, orbit)
+                } else {
+                    Bound::Bounded(orbit)
+                }
+            })
+            .for_each(drop);
+    }
+
+    fn mask() -> Vec<usize> {
+        (0..16).collect()
+    }
+}
+
 impl BoundsChecker<f64> for f64x4 {
     fn check_bounded(x: &[f64], y: &[f64], settings: &BoundsSettings, out: &mut [Bound]) {
         let mut t = [0f64; 4];
@@ -143,29 +524,47 @@ impl BoundsChecker<f64> for f64x4 {
         let y = f64x4::from_slice_aligned(&t);
         let c = (x, y);
         let mut z = (f64x4::splat(0.0), f64x4::splat(0.0));
-        let mut iter = u64x4::splat(0);
-
-        let check = f64x4::splat(4.0);
-        for _ in 0..settings.limit {
+        if settings.start > 0 {
+            let (mut zx, mut zy) = ([0f64; 4], [0f64; 4]);
+            for ((zx, zy), o) in zx.iter_mut().zip(zy.iter_mut()).zip(out.iter()) {
+                *zx = o.orbit().z[0];
+                *zy = o.orbit().z[1];
+            }
             z = (
-                z.0 * z.0 - z.1 * z.1 + c.0,
-                f64x4::splat(2.0) * z.0 * z.1 + c.1,
+                f64x4::from_slice_unaligned(&zx),
+                f64x4::from_slice_unaligned(&zy),
             );
-            let mask = (z.0 * z.0 + z.1 * z.1).lt(check);
-            if mask.none() {
+        }
+        let mut iter = u64x4::splat(settings.start);
+        let mut active = m64x4::splat(true);
+
+        let check = f64x4::splat(settings.radius2());
+        for _ in settings.start..settings.limit {
+            // escaped lanes keep the value they escaped with
+            let next = fractal_step!(settings.fractal, z, c, f64x4::splat(2.0));
+            z = (active.select(next.0, z.0), active.select(next.1, z.1));
+            active &= (z.0 * z.0 + z.1 * z.1).lt(check);
+            if active.none() {
                 break;
             }
-            iter = mask.select(iter + u64x4::splat(1), iter);
+            iter = active.select(iter + u64x4::splat(1), iter);
         }
         let mut checks = [0; 4];
         iter.write_to_slice_aligned(&mut checks);
+        let (mut zx, mut zy) = ([0f64; 4], [0f64; 4]);
+        z.0.write_to_slice_unaligned(&mut zx);
+        z.1.write_to_slice_unaligned(&mut zy);
         out.iter_mut()
-            .zip(checks.iter())
-            .map(|(o, n)| {
+            .zip(checks.iter().zip(zx.iter().zip(zy.iter())))
+            .map(|(o, (n, (zx, zy)))| {
+                let orbit = Orbit {
+                    z: [*zx, *zy],
+                    ..Orbit::default()
+                };
                 *o = if *n < settings.limit {
-                    Bound::Unbounded(*n)
+                    Bound::Unbounded(*n, orbit)
                 } else {
-                    Bound::Bounded
+                    Bound::Bounded(orbit)
                 }
             })
             .for_each(drop);
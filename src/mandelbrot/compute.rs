@@ -1,33 +1,86 @@
-use std::sync::mpsc::{channel, Sender};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::channel,
+    Arc,
+};
 use threadpool::ThreadPool;
 
-use packed_simd::{f32x8, f64x4};
-use rug::{Complex, Float};
+use packed_simd::{f32x16, f32x8, f64x4};
+use rug::{Assign, Complex, Float};
 
-use crate::mandelbrot::bounded::{Bound, BoundsChecker, BoundsSettings};
-use crate::ui::events::ComputeEvent;
+use crate::mandelbrot::bounded::{
+    main_body_period, orbit_path, Bound, BoundsChecker, BoundsSettings, Fractal, Orbit,
+};
+use crate::mandelbrot::double_double::DoubleDouble;
+use crate::mandelbrot::events::{ComputeEvent, Progress};
+use crate::mandelbrot::timing::{RowTimings, WorkerStats};
 
 use num_derive::{FromPrimitive, ToPrimitive};
 
-#[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, FromPrimitive, ToPrimitive)]
 pub enum ComputeEngine {
     Single,
     Double,
     SimdF32x8,
+    SimdF32x16,
     SimdF64x4,
+    DoubleDouble,
     Precision,
 }
 
 impl ComputeEngine {
-    pub const LIST: [Self; 5] = [
+    pub const LIST: [Self; 7] = [
         Self::Single,
         Self::Double,
         Self::SimdF32x8,
+        Self::SimdF32x16,
         Self::SimdF64x4,
+        Self::DoubleDouble,
         Self::Precision,
     ];
+
+    /// Looks up an engine by its `Debug` name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::LIST
+            .iter()
+            .find(|engine| format!("{:?}", engine) == name)
+            .copied()
+    }
+
+    /// Bits of mantissa the engine iterates with, `precision` for the Precision engine. The
+    /// Single engine iterates in `f64` like Double.
+    pub fn mantissa_bits(&self, precision: u32) -> u32 {
+        match self {
+            Self::SimdF32x8 | Self::SimdF32x16 => 24,
+            Self::Single | Self::Double | Self::SimdF64x4 => 53,
+            Self::DoubleDouble => 106,
+            Self::Precision => precision,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ComputeError {
+    /// The progress callback reported that nobody is waiting for the result anymore.
+    Disconnected,
+    /// A worker thread stopped without returning its row.
+    WorkerLost,
+}
+
+impl fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComputeError::Disconnected => write!(f, "nobody is waiting for the result"),
+            ComputeError::WorkerLost => write!(f, "a compute worker stopped unexpectedly"),
+        }
+    }
 }
 
+impl std::error::Error for ComputeError {}
+
+#[derive(Clone)]
 pub struct ComputeSettings {
     x: Float,
     y: Float,
@@ -36,20 +89,18 @@ pub struct ComputeSettings {
     height: u32,
     engine: ComputeEngine,
     bounds: BoundsSettings,
-}
-
-impl Clone for ComputeSettings {
-    fn clone(&self) -> Self {
-        ComputeSettings::new(
-            self.x.clone(),
-            self.y.clone(),
-            self.scale.clone(),
-            self.width,
-            self.height,
-            self.engine,
-            self.bounds,
-        )
-    }
+    stretch: f64,
+    offset: [f64; 2],
+    angle: f64,
+    invert: bool,
+    cancel: Option<Arc<AtomicBool>>,
+    workers: Option<Arc<WorkerStats>>,
+    center_out: bool,
+    coordinates: bool,
+    log_projection: bool,
+    symmetry: bool,
+    progress_steps: u32,
+    precision: u32,
 }
 
 impl ComputeSettings {
@@ -70,29 +121,324 @@ impl ComputeSettings {
             height,
             engine,
             bounds,
+            stretch: 1.0,
+            offset: [0.0, 0.0],
+            angle: 0.0,
+            invert: false,
+            cancel: None,
+            workers: None,
+            center_out: false,
+            coordinates: false,
+            log_projection: false,
+            symmetry: false,
+            progress_steps: 0,
+            precision: bounds.precision,
+        }
+    }
+
+    /// Settings for the rectangle spanning `x[0]..x[1]` horizontally and `y[0]..y[1]`
+    /// vertically, stretching the pixels when its aspect ratio differs from the image.
+    pub fn from_bounds(
+        x: [Float; 2],
+        y: [Float; 2],
+        width: u32,
+        height: u32,
+        engine: ComputeEngine,
+        bounds: BoundsSettings,
+    ) -> ComputeSettings {
+        let precision = bounds.precision;
+        let center = |range: &[Float; 2]| Float::with_val(precision, &range[0] + &range[1]) / 2;
+        let extent = Float::with_val(precision, &x[1] - &x[0]);
+        let scale = Float::with_val(precision, &y[1] - &y[0]);
+        let ratio = Float::with_val(precision, &extent / &scale).to_f64();
+        ComputeSettings::new(center(&x), center(&y), scale, width, height, engine, bounds)
+            .with_stretch(ratio * height as f64 / width as f64)
+    }
+
+    /// Horizontal and vertical `[min, max]` range of the view, the inverse of `from_bounds`.
+    /// The offset and rotation are not taken into account.
+    pub fn rect(&self) -> ([Float; 2], [Float; 2]) {
+        let precision = self.precision;
+        let ratio = self.width as f64 / self.height as f64 * self.stretch;
+        let half_height = Float::with_val(precision, &self.scale / 2);
+        let half_width = Float::with_val(precision, &half_height * ratio);
+        (
+            [
+                Float::with_val(precision, &self.x - &half_width),
+                Float::with_val(precision, &self.x + &half_width),
+            ],
+            [
+                Float::with_val(precision, &self.y - &half_height),
+                Float::with_val(precision, &self.y + &half_height),
+            ],
+        )
+    }
+
+    /// Places the pixels on the plane with `precision` bits instead of the precision of the
+    /// bounds, which the Precision engine iterates at. Deep zooms need the bits to keep the
+    /// pixels apart more than the iteration needs them.
+    pub fn with_precision(mut self, precision: u32) -> ComputeSettings {
+        self.precision = precision;
+        self
+    }
+
+    /// Stretches the horizontal extent of the view independently of the window aspect ratio.
+    pub fn with_stretch(mut self, stretch: f64) -> ComputeSettings {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Shifts every sample point by `offset` pixels, for jittered subsamples.
+    pub fn with_offset(mut self, offset: [f64; 2]) -> ComputeSettings {
+        self.offset = offset;
+        self
+    }
+
+    /// Rotates the view counterclockwise around its center by `angle` radians.
+    pub fn with_angle(mut self, angle: f64) -> ComputeSettings {
+        self.angle = angle;
+        self
+    }
+
+    /// Iterates each pixel `p` at `1/p` instead, turning the plane inside out.
+    pub fn with_invert(mut self, invert: bool) -> ComputeSettings {
+        self.invert = invert;
+        self
+    }
+
+    /// Stops the computation early once `cancel` is set, leaving the remaining rows bounded.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> ComputeSettings {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Keeps `workers` up to date with the rows being computed.
+    pub fn with_workers(mut self, workers: Arc<WorkerStats>) -> ComputeSettings {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Computes the rows from the middle outward, so the center of the image finishes first.
+    pub fn with_center_out(mut self, center_out: bool) -> ComputeSettings {
+        self.center_out = center_out;
+        self
+    }
+
+    /// Point iterated for the pixel at `(x, y)`.
+    fn transform(&self, x: Float, y: &Float) -> (Float, Float) {
+        let precision = self.precision;
+        let (x, y) = if self.log_projection {
+            let turn = 2.0 * std::f64::consts::PI;
+            let u = Float::with_val(precision, &x - &self.x) / &self.scale;
+            let v = Float::with_val(precision, y - &self.y) / &self.scale;
+            let radius = Float::with_val(precision, &self.scale * (u.to_f64() * turn).exp());
+            let (sin, cos) = (v.to_f64() * turn).sin_cos();
+            (
+                Float::with_val(precision, &radius * cos) + &self.x,
+                Float::with_val(precision, &radius * sin) + &self.y,
+            )
+        } else {
+            (x, y.clone())
+        };
+        let (x, y) = if self.angle == 0.0 {
+            (x, y)
+        } else {
+            let (sin, cos) = self.angle.sin_cos();
+            let u = Float::with_val(precision, &x - &self.x);
+            let v = Float::with_val(precision, y - &self.y);
+            (
+                Float::with_val(precision, &u * cos) - Float::with_val(precision, &v * sin)
+                    + &self.x,
+                Float::with_val(precision, &u * sin)
+                    + Float::with_val(precision, &v * cos)
+                    + &self.y,
+            )
+        };
+        if !self.invert {
+            return (x, y);
+        }
+        let norm = Float::with_val(precision, &x * &x) + Float::with_val(precision, &y * &y);
+        // the origin maps to infinity, any far away point escapes just the same
+        if norm.is_zero() {
+            return (Float::with_val(precision, 1e10), Float::new(precision));
+        }
+        (
+            Float::with_val(precision, &x / &norm),
+            -Float::with_val(precision, &y / &norm),
+        )
+    }
+
+    /// Shows the exponential map around the center: the height of the view is one turn
+    /// around it and the width runs over the logarithm of the distance to it, with pixels as
+    /// wide in log distance as in angle. The middle column lies at a distance of `scale`.
+    pub fn with_log_projection(mut self, log_projection: bool) -> ComputeSettings {
+        self.log_projection = log_projection;
+        self
+    }
+
+    /// Stores the point of every pixel in its orbit instead of iterating it, for checking the
+    /// mapping of pixels onto the plane.
+    pub fn with_coordinates(mut self, coordinates: bool) -> ComputeSettings {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Copies the rows below the real axis from their mirror image above it when the view
+    /// allows it, instead of iterating them.
+    pub fn with_symmetry(mut self, symmetry: bool) -> ComputeSettings {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Sends at most `steps` progress events per computed set instead of one per row, 0 to
+    /// report every row.
+    pub fn with_progress_steps(mut self, steps: u32) -> ComputeSettings {
+        self.progress_steps = steps;
+        self
+    }
+
+    /// Rows in the order they are computed, leaving out the ones filled by `mirrored_rows`.
+    fn row_order(&self, mirrored: &[(u32, u32)]) -> Vec<u32> {
+        let mut rows: Vec<u32> = (0..self.height)
+            .filter(|y| mirrored.iter().all(|&(row, _)| row != *y))
+            .collect();
+        if self.center_out {
+            let middle = self.height as i64 / 2;
+            rows.sort_by_key(|&y| ((y as i64 - middle).abs(), y));
+        }
+        rows
+    }
+
+    /// Pairs of a row and the row it mirrors across the real axis, for the grid starting at
+    /// `start` with `step` between rows. Empty unless symmetry is enabled and both the formula
+    /// and the mapping of pixels onto the plane are symmetric, with the axis falling exactly
+    /// on a row or halfway between two.
+    fn mirrored_rows(&self, start: &Float, step: &Float) -> Vec<(u32, u32)> {
+        if !self.symmetry
+            || self.bounds.fractal == Fractal::BurningShip
+            || self.angle != 0.0
+            || self.log_projection
+            || self.coordinates
+        {
+            return Vec::new();
+        }
+        // rows y and axis - y lie on opposite sides of the real axis
+        let axis = Float::with_val(self.precision, start / step) * -2;
+        let rounded = Float::with_val(self.precision, axis.round_ref());
+        if Float::with_val(self.precision, &axis - &rounded).abs() > 1e-6 {
+            return Vec::new();
+        }
+        let axis = match rounded.to_i32_saturating() {
+            Some(axis) if axis > 0 => axis as i64,
+            _ => return Vec::new(),
+        };
+        (0..self.height)
+            .filter(|&y| 2 * (y as i64) < axis && axis - (y as i64) < self.height as i64)
+            .map(|y| (y, (axis - y as i64) as u32))
+            .collect()
+    }
+
+    /// Whether neighbouring pixels lie closer together than the engine can tell apart at the
+    /// coordinates of the view, which shows as blocks of identical pixels. The points are
+    /// placed at the coordinate precision, so that limits every engine as well.
+    pub fn exceeds_precision(&self) -> bool {
+        let (start, step) = Compute::pixel_grid(self);
+        let bits = self
+            .engine
+            .mantissa_bits(self.bounds.precision)
+            .min(self.precision) as i32;
+        let size = [self.width, self.height];
+        (0..2).any(|i| {
+            let end = Float::with_val(self.precision, &step[i] * size[i]) + &start[i];
+            let magnitude = start[i].to_f64().abs().max(end.to_f64().abs());
+            // spacing of the floats around the largest coordinate on this axis
+            let ulp = magnitude * 2f64.powi(1 - bits);
+            step[i].to_f64() < ulp
+        })
+    }
+
+    fn cancelled(&self) -> bool {
+        match &self.cancel {
+            Some(cancel) => cancel.load(Ordering::Relaxed),
+            None => false,
         }
     }
 }
 
+/// Well mixed 64 bits derived from `x` alone, consecutive inputs give unrelated outputs.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Element `index` of the van der Corput sequence in `base`, evenly filling 0..1.
+fn halton(mut index: u32, base: u32) -> f64 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f64;
+        result += fraction * (index % base) as f64;
+        index /= base;
+    }
+    result
+}
+
+/// Offset in -0.5..0.5 pixels of subsample `index`, for use with `with_offset`.
+///
+/// Follows the 2-3 Halton sequence shifted by an amount derived from `seed` only, so
+/// the same seed always gives the same subsamples.
+pub fn subpixel_offset(seed: u64, index: u32) -> [f64; 2] {
+    let z = splitmix64(seed);
+    let shift = [
+        (z >> 32) as f64 / (1u64 << 32) as f64,
+        (z & 0xffff_ffff) as f64 / (1u64 << 32) as f64,
+    ];
+    [
+        (halton(index, 2) + shift[0]).fract() - 0.5,
+        (halton(index, 3) + shift[1]).fract() - 0.5,
+    ]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SetStats {
+    pub bounded: usize,
+    pub unbounded: usize,
+}
+
+#[derive(Clone)]
 pub struct ComputedSet {
     width: u32,
     height: u32,
+    limit: u64,
+    bailout: f64,
     data: Option<Vec<Bound>>,
 }
 
 impl ComputedSet {
-    pub fn new(width: u32, height: u32, data: Vec<Bound>) -> ComputedSet {
+    pub fn new(width: u32, height: u32, limit: u64, data: Vec<Bound>) -> ComputedSet {
         ComputedSet {
             width,
             height,
+            limit,
+            bailout: 2.0,
             data: Some(data),
         }
     }
 
+    /// Records the escape radius the set was computed with, 2 when not set.
+    pub fn with_bailout(mut self, bailout: f64) -> ComputedSet {
+        self.bailout = bailout;
+        self
+    }
+
     pub fn empty(width: u32, height: u32) -> ComputedSet {
         ComputedSet {
             width,
             height,
+            limit: 0,
+            bailout: 2.0,
             data: None,
         }
     }
@@ -101,12 +447,205 @@ impl ComputedSet {
         (self.width, self.height)
     }
 
+    /// Iteration limit the set was computed with.
+    pub fn get_limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn get_bailout(&self) -> f64 {
+        self.bailout
+    }
+
+    /// Pixel at column `x` and row `y`, rows counting up from the bottom of the image.
+    pub fn get(&self, x: u32, y: u32) -> Option<Bound> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.data
+            .as_ref()
+            .map(|data| data[(y * self.width + x) as usize])
+    }
+
     pub fn iter(&self) -> Option<std::slice::Iter<Bound>> {
         match &self.data {
             Some(data) => Some(data.iter()),
             None => None,
         }
     }
+
+    /// Counts of escaped pixels in `bins` equal ranges of escape iteration up to the limit.
+    pub fn histogram(&self, bins: usize) -> Vec<f32> {
+        let mut histogram = vec![0.0; bins];
+        if let Some(data) = &self.data {
+            let limit = self.limit.max(1);
+            for bound in data {
+                if let Bound::Unbounded(n, _) = bound {
+                    let bin = (*n * bins as u64 / limit) as usize;
+                    histogram[bin.min(bins - 1)] += 1.0;
+                }
+            }
+        }
+        histogram
+    }
+
+    /// Number of bounded and escaped pixels, both zero for an empty set.
+    pub fn stats(&self) -> SetStats {
+        let mut stats = SetStats::default();
+        if let Some(data) = &self.data {
+            for bound in data {
+                match bound {
+                    Bound::Bounded(_) => stats.bounded += 1,
+                    Bound::Unbounded(..) => stats.unbounded += 1,
+                }
+            }
+        }
+        stats
+    }
+
+    const MAGIC: &[u8; 4] = b"MBCS";
+    const VERSION: u8 = 2;
+    const BOUNDED: u64 = std::u64::MAX;
+    /// Largest set `load` accepts, far beyond any resolution the explorer renders.
+    const MAX_PIXELS: usize = 1 << 30;
+
+    /// Writes the set as a format version, its size, limit and bailout followed by a
+    /// run-length-encoded iteration buffer, each run being a `u32` length and a `u64`
+    /// iteration count (`u64::MAX` for bounded). The orbit of every pixel follows, as the
+    /// `f64`s `z`, `stripe`, `stripe_previous` and `dz` and the `u32` period, so the set can
+    /// be colored in every mode after loading.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&[Self::VERSION])?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.limit.to_le_bytes())?;
+        writer.write_all(&self.bailout.to_le_bytes())?;
+        let data = match &self.data {
+            Some(data) => data,
+            None => {
+                writer.write_all(&[0])?;
+                return Ok(());
+            }
+        };
+        writer.write_all(&[1])?;
+
+        let value = |bound: &Bound| match bound {
+            Bound::Bounded(_) => Self::BOUNDED,
+            Bound::Unbounded(n, _) => *n,
+        };
+        let mut runs = data.iter().map(value);
+        if let Some(mut current) = runs.next() {
+            let mut length = 1u32;
+            for n in runs {
+                if n == current && length < std::u32::MAX {
+                    length += 1;
+                } else {
+                    writer.write_all(&length.to_le_bytes())?;
+                    writer.write_all(&current.to_le_bytes())?;
+                    current = n;
+                    length = 1;
+                }
+            }
+            writer.write_all(&length.to_le_bytes())?;
+            writer.write_all(&current.to_le_bytes())?;
+        }
+        for bound in data {
+            let orbit = bound.orbit();
+            let values = [
+                orbit.z[0],
+                orbit.z[1],
+                orbit.stripe,
+                orbit.stripe_previous,
+                orbit.dz[0],
+                orbit.dz[1],
+            ];
+            for value in &values {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&orbit.period.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<ComputedSet> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(invalid("not a computed set"));
+        }
+        let mut flag = [0u8; 1];
+        reader.read_exact(&mut flag)?;
+        if flag[0] != Self::VERSION {
+            return Err(invalid("unsupported computed set version"));
+        }
+        let mut word = [0u8; 4];
+        reader.read_exact(&mut word)?;
+        let width = u32::from_le_bytes(word);
+        reader.read_exact(&mut word)?;
+        let height = u32::from_le_bytes(word);
+        let mut long = [0u8; 8];
+        reader.read_exact(&mut long)?;
+        let limit = u64::from_le_bytes(long);
+        reader.read_exact(&mut long)?;
+        let bailout = f64::from_le_bytes(long);
+        reader.read_exact(&mut flag)?;
+        if flag[0] == 0 {
+            return Ok(ComputedSet::empty(width, height).with_bailout(bailout));
+        }
+
+        // the header is not trusted, the buffer only grows as the runs are decoded
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&size| size <= Self::MAX_PIXELS)
+            .ok_or_else(|| invalid("size out of range"))?;
+        let truncated = |e: io::Error| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid("runs cover fewer pixels than the size"),
+            _ => e,
+        };
+        let mut data = Vec::new();
+        while data.len() < size {
+            reader.read_exact(&mut word).map_err(truncated)?;
+            reader.read_exact(&mut long).map_err(truncated)?;
+            let length = u32::from_le_bytes(word) as usize;
+            if length == 0 || length > size - data.len() {
+                return Err(invalid("runs cover more pixels than the size"));
+            }
+            let bound = match u64::from_le_bytes(long) {
+                Self::BOUNDED => Bound::Bounded(Orbit::default()),
+                n => Bound::Unbounded(n, Orbit::default()),
+            };
+            data.extend(std::iter::repeat(bound).take(length));
+        }
+        let missing = |e: io::Error| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid("orbits missing for some pixels"),
+            _ => e,
+        };
+        for bound in data.iter_mut() {
+            let mut values = [0.0; 6];
+            for value in values.iter_mut() {
+                reader.read_exact(&mut long).map_err(missing)?;
+                *value = f64::from_le_bytes(long);
+            }
+            reader.read_exact(&mut word).map_err(missing)?;
+            let orbit = Orbit {
+                z: [values[0], values[1]],
+                period: u32::from_le_bytes(word),
+                stripe: values[2],
+                stripe_previous: values[3],
+                dz: [values[4], values[5]],
+            };
+            *bound = match *bound {
+                Bound::Bounded(_) => Bound::Bounded(orbit),
+                Bound::Unbounded(n, _) => Bound::Unbounded(n, orbit),
+            };
+        }
+        if reader.read(&mut flag)? != 0 {
+            return Err(invalid("data past the last orbit"));
+        }
+        Ok(ComputedSet::new(width, height, limit, data).with_bailout(bailout))
+    }
 }
 
 pub struct Compute {}
@@ -114,212 +653,1077 @@ pub struct Compute {}
 impl Compute {
     pub fn compute_set(
         thread_pool: Option<&mut ThreadPool>,
-        message: Option<Sender<ComputeEvent>>,
+        progress: Progress,
         settings: &ComputeSettings,
-    ) -> ComputedSet {
+    ) -> Result<ComputedSet, ComputeError> {
         match settings.engine {
             ComputeEngine::Single => {
-                Self::compute_set_with_engine::<f32>(thread_pool, message, &settings)
+                Self::compute_set_with_engine::<f32>(thread_pool, progress, &settings, None)
             }
             ComputeEngine::Double => {
-                Self::compute_set_with_engine::<f64>(thread_pool, message, &settings)
+                Self::compute_set_with_engine::<f64>(thread_pool, progress, &settings, None)
             }
             ComputeEngine::Precision => {
-                Self::compute_set_with_engine_hp::<Complex>(thread_pool, message, &settings)
+                Self::compute_set_with_engine_hp::<Complex>(thread_pool, progress, &settings)
             }
             ComputeEngine::SimdF32x8 => {
-                Self::compute_set_with_engine::<f32x8>(thread_pool, message, &settings)
+                Self::compute_set_with_engine::<f32x8>(thread_pool, progress, &settings, None)
+            }
+            ComputeEngine::SimdF32x16 => {
+                Self::compute_set_with_engine::<f32x16>(thread_pool, progress, &settings, None)
             }
             ComputeEngine::SimdF64x4 => {
-                Self::compute_set_with_engine::<f64x4>(thread_pool, message, &settings)
+                Self::compute_set_with_engine::<f64x4>(thread_pool, progress, &settings, None)
+            }
+            ComputeEngine::DoubleDouble => {
+                Self::compute_set_with_engine_hp::<DoubleDouble>(thread_pool, progress, &settings)
             }
         }
     }
 
-    fn compute_set_with_engine<T: BoundsChecker<f64> + 'static>(
+    /// Renders the Buddhabrot, how often the orbits of `samples` random escaping points with
+    /// `|c| < 2` pass through every pixel. The counts are stored as escape counts with the
+    /// highest one as limit, so the iteration coloring shows the density. Iterates in f64 and
+    /// ignores the rotation and inversion of the view.
+    pub fn compute_buddhabrot(
         thread_pool: Option<&mut ThreadPool>,
-        message: Option<Sender<ComputeEvent>>,
+        progress: Progress,
         settings: &ComputeSettings,
-    ) -> ComputedSet {
-        let ratio = settings.width as f64 / settings.height as f64;
-        let scale = settings.scale.to_f64();
+        samples: u64,
+        seed: u64,
+    ) -> Result<ComputedSet, ComputeError> {
+        const CHUNKS: u32 = 64;
+        let (start, step) = Self::pixel_grid(settings);
+        let grid = [
+            start[0].to_f64(),
+            start[1].to_f64(),
+            step[0].to_f64(),
+            step[1].to_f64(),
+        ];
 
-        let x_start = settings.x.to_f64() - ((scale * ratio) / 2.0);
-        let y_start = settings.y.to_f64() - (scale / 2.0);
-        let step = (scale * ratio) / (settings.width as f64);
+        Self::send(progress, ComputeEvent::Start)?;
 
-        if let Some(sender) = &message {
-            sender.send(ComputeEvent::Start).unwrap();
+        let size = [settings.width, settings.height];
+        let range = |chunk: u32| {
+            samples * chunk as u64 / CHUNKS as u64..samples * (chunk as u64 + 1) / CHUNKS as u64
+        };
+        let mut density = vec![0u64; settings.width as usize * settings.height as usize];
+        let mut add = |counts: Vec<u64>| {
+            for (total, count) in density.iter_mut().zip(counts) {
+                *total += count;
+            }
+        };
+        match thread_pool {
+            None => {
+                for chunk in 0..CHUNKS {
+                    if settings.cancelled() {
+                        break;
+                    }
+                    add(Self::buddhabrot_chunk(
+                        &settings.bounds,
+                        grid,
+                        size,
+                        range(chunk),
+                        seed,
+                    ));
+                    Self::send(progress, ComputeEvent::Progress((chunk, CHUNKS)))?;
+                }
+            }
+            Some(thread_pool) => {
+                let (tx, rx) = channel();
+                for chunk in 0..CHUNKS {
+                    let tx = tx.clone();
+                    let settings = settings.clone();
+                    let range = range(chunk);
+                    thread_pool.execute(move || {
+                        if !settings.cancelled() {
+                            let counts =
+                                Self::buddhabrot_chunk(&settings.bounds, grid, size, range, seed);
+                            // nobody listens anymore when the compute was aborted
+                            tx.send(counts).ok();
+                        }
+                    });
+                }
+                drop(tx);
+                for n in 0..CHUNKS {
+                    match rx.recv() {
+                        Ok(counts) => add(counts),
+                        // cancelled jobs leave without sending anything
+                        Err(_) if settings.cancelled() => break,
+                        Err(_) => return Err(ComputeError::WorkerLost),
+                    }
+                    Self::send(progress, ComputeEvent::Progress((n, CHUNKS)))?;
+                }
+            }
         }
 
-        let mut output = vec![Bound::Bounded; settings.width as usize * settings.height as usize];
+        Self::send(progress, ComputeEvent::End)?;
+        let limit = density.iter().copied().max().unwrap_or(0).max(1);
+        let data = density
+            .into_iter()
+            .map(|count| Bound::Unbounded(count, Orbit::default()))
+            .collect();
+        Ok(ComputedSet::new(
+            settings.width,
+            settings.height,
+            limit,
+            data,
+        ))
+    }
+
+    /// Visit counts per pixel of the samples in `range`, `grid` being the lower left pixel
+    /// center followed by the pixel step.
+    fn buddhabrot_chunk(
+        bounds: &BoundsSettings,
+        grid: [f64; 4],
+        size: [u32; 2],
+        range: std::ops::Range<u64>,
+        seed: u64,
+    ) -> Vec<u64> {
+        let [width, height] = size;
+        let mut density = vec![0u64; width as usize * height as usize];
+        let radius2 = bounds.bailout * bounds.bailout;
+        let uniform = |bits: u64| (bits >> 11) as f64 / (1u64 << 53) as f64 * 4.0 - 2.0;
+        for sample in range {
+            let c = [
+                uniform(splitmix64(seed ^ sample.wrapping_mul(2))),
+                uniform(splitmix64(seed ^ sample.wrapping_mul(2).wrapping_add(1))),
+            ];
+            if c[0] * c[0] + c[1] * c[1] >= 4.0 {
+                continue;
+            }
+            if bounds.fractal == Fractal::Mandelbrot {
+                // the main cardioid and the period 2 bulb never escape, skip iterating them
+                let q = (c[0] - 0.25) * (c[0] - 0.25) + c[1] * c[1];
+                let bulb = (c[0] + 1.0) * (c[0] + 1.0) + c[1] * c[1];
+                if q * (q + c[0] - 0.25) <= c[1] * c[1] / 4.0 || bulb <= 1.0 / 16.0 {
+                    continue;
+                }
+            }
+            let path = orbit_path(c, bounds, bounds.limit as usize);
+            let escaped = path
+                .last()
+                .map_or(false, |z| z[0] * z[0] + z[1] * z[1] >= radius2);
+            if !escaped {
+                continue;
+            }
+            for z in &path[1..] {
+                let x = ((z[0] - grid[0]) / grid[2]).round();
+                let y = ((z[1] - grid[1]) / grid[3]).round();
+                if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+                    density[y as usize * width as usize + x as usize] += 1;
+                }
+            }
+        }
+        density
+    }
+
+    /// Iterates the single point `x + yi` from zero with the checker of `engine`.
+    pub fn compute_point(
+        x: &Float,
+        y: &Float,
+        settings: &BoundsSettings,
+        engine: ComputeEngine,
+    ) -> Bound {
+        let settings = BoundsSettings {
+            start: 0,
+            ..*settings
+        };
+        match engine {
+            ComputeEngine::Single => Self::check_point::<f32>(x, y, &settings),
+            ComputeEngine::Double => Self::check_point::<f64>(x, y, &settings),
+            ComputeEngine::SimdF32x8 => Self::check_point::<f32x8>(x, y, &settings),
+            ComputeEngine::SimdF32x16 => Self::check_point::<f32x16>(x, y, &settings),
+            ComputeEngine::SimdF64x4 => Self::check_point::<f64x4>(x, y, &settings),
+            ComputeEngine::DoubleDouble => Self::check_point_hp::<DoubleDouble>(x, y, &settings),
+            ComputeEngine::Precision => Self::check_point_hp::<Complex>(x, y, &settings),
+        }
+    }
+
+    /// Fills every lane of a vectorized checker with the same point and keeps the first.
+    fn check_point<T: BoundsChecker<f64>>(
+        x: &Float,
+        y: &Float,
+        settings: &BoundsSettings,
+    ) -> Bound {
+        let lanes = T::mask().len();
+        let mut out = vec![Bound::Bounded(Orbit::default()); lanes];
+        T::check_bounded(
+            &vec![x.to_f64(); lanes],
+            &vec![y.to_f64(); lanes],
+            settings,
+            &mut out,
+        );
+        out[0]
+    }
+
+    fn check_point_hp<T: BoundsChecker<Float>>(
+        x: &Float,
+        y: &Float,
+        settings: &BoundsSettings,
+    ) -> Bound {
+        let lanes = T::mask().len();
+        let mut out = vec![Bound::Bounded(Orbit::default()); lanes];
+        T::check_bounded(
+            &vec![x.clone(); lanes],
+            &vec![y.clone(); lanes],
+            settings,
+            &mut out,
+        );
+        out[0]
+    }
+
+    /// Raises the iteration limit of `previous`, computed for the same view with
+    /// `previous_limit` iterations, by only continuing the orbits of its bounded pixels.
+    /// The Precision engine can't resume from the stored orbits and computes the full set.
+    pub fn extend_set(
+        thread_pool: Option<&mut ThreadPool>,
+        progress: Progress,
+        settings: &ComputeSettings,
+        previous: &ComputedSet,
+        previous_limit: u64,
+    ) -> Result<ComputedSet, ComputeError> {
+        if previous.data.is_none()
+            || previous.get_size() != (settings.width, settings.height)
+            || previous_limit >= settings.bounds.limit
+        {
+            return Self::compute_set(thread_pool, progress, settings);
+        }
+        let mut resume = settings.clone();
+        resume.bounds.start = previous_limit;
+        let previous = Some(previous);
+        match settings.engine {
+            ComputeEngine::Single => {
+                Self::compute_set_with_engine::<f32>(thread_pool, progress, &resume, previous)
+            }
+            ComputeEngine::Double => {
+                Self::compute_set_with_engine::<f64>(thread_pool, progress, &resume, previous)
+            }
+            ComputeEngine::DoubleDouble | ComputeEngine::Precision => {
+                Self::compute_set(thread_pool, progress, settings)
+            }
+            ComputeEngine::SimdF32x8 => {
+                Self::compute_set_with_engine::<f32x8>(thread_pool, progress, &resume, previous)
+            }
+            ComputeEngine::SimdF32x16 => {
+                Self::compute_set_with_engine::<f32x16>(thread_pool, progress, &resume, previous)
+            }
+            ComputeEngine::SimdF64x4 => {
+                Self::compute_set_with_engine::<f64x4>(thread_pool, progress, &resume, previous)
+            }
+        }
+    }
+
+    fn compute_set_with_engine<T: BoundsChecker<f64> + 'static>(
+        thread_pool: Option<&mut ThreadPool>,
+        progress: Progress,
+        settings: &ComputeSettings,
+        previous: Option<&ComputedSet>,
+    ) -> Result<ComputedSet, ComputeError> {
+        let (start, step) = Self::pixel_grid(settings);
+        let mirrored = settings.mirrored_rows(&start[1], &step[1]);
+        let rows = settings.row_order(&mirrored);
+        let total = rows.len() as u32;
+
+        Self::send(progress, ComputeEvent::Start)?;
+
+        let mut output = match previous.and_then(|previous| previous.data.as_ref()) {
+            Some(data) => data.clone(),
+            None => vec![
+                Bound::Bounded(Orbit::default());
+                settings.width as usize * settings.height as usize
+            ],
+        };
+        let timings = RowTimings::default();
         match thread_pool {
             None => {
-                for y in 0..settings.height {
+                for (n, &y) in (0..).zip(&rows) {
+                    if settings.cancelled() {
+                        break;
+                    }
                     let out = &mut output
                         [(y * settings.width) as usize..((y + 1) * settings.width) as usize];
-                    Self::compute_row::<T>(y, [x_start, y_start], step, out, &settings);
-                    if let Some(sender) = &message {
-                        sender
-                            .send(ComputeEvent::Progress((y, settings.height)))
-                            .unwrap();
-                    }
+                    timings.time(|| {
+                        Self::compute_row::<T>(
+                            y,
+                            [&start[0], &start[1]],
+                            [&step[0], &step[1]],
+                            out,
+                            &settings,
+                        )
+                    });
+                    Self::send_row(progress, settings, n, total)?;
                 }
             }
             Some(thread_pool) => {
                 let (tx, rx) = channel();
-                for y in 0..settings.height {
+                for &y in &rows {
                     let tx = tx.clone();
                     let settings = settings.clone();
+                    let timings = timings.clone();
+                    let start = start.clone();
+                    let step = step.clone();
+                    let mut out = output
+                        [(y * settings.width) as usize..((y + 1) * settings.width) as usize]
+                        .to_vec();
                     thread_pool.execute(move || {
-                        let mut out = vec![Bound::Bounded; settings.width as usize];
-                        Self::compute_row::<T>(y, [x_start, y_start], step, &mut out, &settings);
-                        tx.send((y, out)).unwrap();
+                        if !settings.cancelled() {
+                            timings.time(|| {
+                                Self::compute_row::<T>(
+                                    y,
+                                    [&start[0], &start[1]],
+                                    [&step[0], &step[1]],
+                                    &mut out,
+                                    &settings,
+                                )
+                            });
+                        }
+                        // nobody listens anymore when the compute was aborted
+                        tx.send((y, out)).ok();
                     });
                 }
-                for n in 0..settings.height {
-                    let (y, row) = rx.recv().unwrap();
+                drop(tx);
+                for n in 0..total {
+                    let (y, row) = rx.recv().map_err(|_| ComputeError::WorkerLost)?;
                     for (input, output) in row
                         .iter()
                         .zip(output.iter_mut().skip((y * settings.width) as usize))
                     {
                         *output = *input;
                     }
-                    if let Some(sender) = &message {
-                        sender
-                            .send(ComputeEvent::Progress((n, settings.height)))
-                            .unwrap();
-                    }
+                    Self::send_row(progress, settings, n, total)?;
                 }
             }
         }
-        if let Some(sender) = &message {
-            sender.send(ComputeEvent::End).unwrap();
-        }
-        ComputedSet::new(settings.width, settings.height, output)
+        // only the scalar engines fill in the stripe averages
+        let stripes = settings.bounds.stripe_density > 0.0 && T::mask().len() == 1;
+        Self::fill_mirrored(&mut output, settings.width, &mirrored, stripes);
+        timings.report();
+        Self::send(progress, ComputeEvent::End)?;
+        Ok(ComputedSet::new(
+            settings.width,
+            settings.height,
+            settings.bounds.limit,
+            output,
+        )
+        .with_bailout(settings.bounds.bailout))
     }
 
     fn compute_set_with_engine_hp<T: BoundsChecker<Float> + 'static>(
         thread_pool: Option<&mut ThreadPool>,
-        message: Option<Sender<ComputeEvent>>,
+        progress: Progress,
         settings: &ComputeSettings,
-    ) -> ComputedSet {
-        let precision = settings.bounds.precision;
+    ) -> Result<ComputedSet, ComputeError> {
+        let (start, step) = Self::pixel_grid(settings);
+        let mirrored = settings.mirrored_rows(&start[1], &step[1]);
+        let rows = settings.row_order(&mirrored);
+        let total = rows.len() as u32;
 
-        let w = Float::with_val(precision, settings.width);
-        let h = Float::with_val(precision, settings.height);
-        let ratio = Float::with_val(precision, &w / &h);
+        Self::send(progress, ComputeEvent::Start)?;
 
-        let x_start = Float::with_val(
-            precision,
-            &settings.x - (Float::with_val(precision, &settings.scale * &ratio) / 2.0),
-        );
-        let y_start = Float::with_val(
-            precision,
-            &settings.y - (Float::with_val(precision, &settings.scale / 2.0)),
-        );
-        let step = Float::with_val(precision, &settings.scale * &ratio) / &w;
-
-        if let Some(sender) = &message {
-            sender.send(ComputeEvent::Start).unwrap();
-        }
-
-        let mut output = vec![Bound::Bounded; settings.width as usize * settings.height as usize];
+        let mut output = vec![
+            Bound::Bounded(Orbit::default());
+            settings.width as usize * settings.height as usize
+        ];
+        let timings = RowTimings::default();
         match thread_pool {
             None => {
-                for y in 0..settings.height {
+                for (n, &y) in (0..).zip(&rows) {
+                    if settings.cancelled() {
+                        break;
+                    }
                     let out = &mut output
                         [(y * settings.width) as usize..((y + 1) * settings.width) as usize];
-                    Self::compute_row_hp::<T>(y, [&x_start, &y_start], &step, out, &settings);
-                    if let Some(sender) = &message {
-                        sender
-                            .send(ComputeEvent::Progress((y, settings.height)))
-                            .unwrap();
-                    }
+                    timings.time(|| {
+                        Self::compute_row_hp::<T>(
+                            y,
+                            [&start[0], &start[1]],
+                            [&step[0], &step[1]],
+                            out,
+                            &settings,
+                        )
+                    });
+                    Self::send_row(progress, settings, n, total)?;
                 }
             }
             Some(thread_pool) => {
                 let (tx, rx) = channel();
-                for y in 0..settings.height {
+                for &y in &rows {
                     let tx = tx.clone();
                     let settings = settings.clone();
-                    let x_start = x_start.clone();
-                    let y_start = y_start.clone();
+                    let start = start.clone();
                     let step = step.clone();
+                    let timings = timings.clone();
                     thread_pool.execute(move || {
-                        let mut out = vec![Bound::Bounded; settings.width as usize];
-                        Self::compute_row_hp::<T>(
-                            y,
-                            [&x_start, &y_start],
-                            &step,
-                            &mut out,
-                            &settings,
-                        );
-                        tx.send((y, out)).unwrap();
+                        let mut out =
+                            vec![Bound::Bounded(Orbit::default()); settings.width as usize];
+                        if !settings.cancelled() {
+                            timings.time(|| {
+                                Self::compute_row_hp::<T>(
+                                    y,
+                                    [&start[0], &start[1]],
+                                    [&step[0], &step[1]],
+                                    &mut out,
+                                    &settings,
+                                )
+                            });
+                        }
+                        // nobody listens anymore when the compute was aborted
+                        tx.send((y, out)).ok();
                     });
                 }
-                for n in 0..settings.height {
-                    let (y, row) = rx.recv().unwrap();
+                drop(tx);
+                for n in 0..total {
+                    let (y, row) = rx.recv().map_err(|_| ComputeError::WorkerLost)?;
                     for (input, output) in row
                         .iter()
                         .zip(output.iter_mut().skip((y * settings.width) as usize))
                     {
                         *output = *input;
                     }
-                    if let Some(sender) = &message {
-                        sender
-                            .send(ComputeEvent::Progress((n, settings.height)))
-                            .unwrap();
-                    }
+                    Self::send_row(progress, settings, n, total)?;
                 }
             }
         }
-        if let Some(sender) = &message {
-            sender.send(ComputeEvent::End).unwrap();
+        Self::fill_mirrored(&mut output, settings.width, &mirrored, false);
+        timings.report();
+        Self::send(progress, ComputeEvent::End)?;
+        Ok(ComputedSet::new(
+            settings.width,
+            settings.height,
+            settings.bounds.limit,
+            output,
+        )
+        .with_bailout(settings.bounds.bailout))
+    }
+
+    fn send(progress: Progress, event: ComputeEvent) -> Result<(), ComputeError> {
+        match progress {
+            Some(progress) if !progress(event) => Err(ComputeError::Disconnected),
+            _ => Ok(()),
         }
-        ComputedSet::new(settings.width, settings.height, output)
+    }
+
+    /// Reports row `n` of `total` done, unless it falls within the same of the settings'
+    /// progress steps as the row before it.
+    fn send_row(
+        progress: Progress,
+        settings: &ComputeSettings,
+        n: u32,
+        total: u32,
+    ) -> Result<(), ComputeError> {
+        let steps = settings.progress_steps as u64;
+        let step = |n: u32| n as u64 * steps / total as u64;
+        if steps == 0 || n + 1 == total || step(n + 1) != step(n) {
+            Self::send(progress, ComputeEvent::Progress((n, total)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Lower left corner and per-pixel step of the pixel grid, at the settings' precision.
+    fn pixel_grid(settings: &ComputeSettings) -> ([Float; 2], [Float; 2]) {
+        let precision = settings.precision;
+
+        let w = Float::with_val(precision, settings.width);
+        let h = Float::with_val(precision, settings.height);
+        let ratio = Float::with_val(precision, &w / &h) * settings.stretch;
+
+        let x_start = Float::with_val(
+            precision,
+            &settings.x - (Float::with_val(precision, &settings.scale * &ratio) / 2.0),
+        );
+        let y_start = Float::with_val(
+            precision,
+            &settings.y - (Float::with_val(precision, &settings.scale / 2.0)),
+        );
+        let step = [
+            Float::with_val(precision, &settings.scale * &ratio) / &w,
+            Float::with_val(precision, &settings.scale / &h),
+        ];
+        let x_start = x_start + Float::with_val(precision, &step[0] * settings.offset[0]);
+        let y_start = y_start + Float::with_val(precision, &step[1] * settings.offset[1]);
+        ([x_start, y_start], step)
     }
 
     fn compute_row<T: BoundsChecker<f64> + 'static>(
         y: u32,
-        start: [f64; 2],
-        step: f64,
+        start: [&Float; 2],
+        step: [&Float; 2],
         out: &mut [Bound],
         settings: &ComputeSettings,
     ) {
+        let _busy = settings.workers.as_ref().map(|workers| workers.start_row());
         let step_by = T::mask().len();
-        let yy = start[1] + step * y as f64;
-        for x in (0..settings.width).step_by(step_by) {
-            let mut xx: Vec<f64> = Vec::with_capacity(step_by);
-            for i in 0..step_by {
-                xx.push(start[0] + step * (x + i as u32) as f64)
-            }
-            let yy = vec![yy; step_by];
+        let precision = settings.precision;
+        // pixel coordinates are placed at full precision, only the iteration runs in f64
+        let row = Float::with_val(
+            precision,
+            start[1] + Float::with_val(precision, step[1] * y),
+        );
+        // a plain grid needs no transform, and no allocation per pixel to place its points
+        let plain = settings.angle == 0.0 && !settings.invert && !settings.log_projection;
+        let row_y = row.to_f64();
+        let mut px = Float::new(precision);
+        let (xx, yy): (Vec<f64>, Vec<f64>) = (0..settings.width)
+            .map(|pixel| {
+                px.assign(step[0] * pixel);
+                px += start[0];
+                if plain {
+                    (px.to_f64(), row_y)
+                } else {
+                    let (x, y) = settings.transform(px.clone(), &row);
+                    (x.to_f64(), y.to_f64())
+                }
+            })
+            .unzip();
+        if settings.coordinates {
+            Self::store_coordinates(&xx, &yy, out);
+            return;
+        }
 
-            let out = &mut out[x as usize..x as usize + step_by];
-            T::check_bounded(&xx, &yy, &settings.bounds, out);
+        // the runs of the row inside the main cardioid or period 2 bulb are bounded without
+        // iterating, only the pixels left over are packed into lane groups
+        let mandelbrot = settings.bounds.fractal == Fractal::Mandelbrot;
+        let mut pending = Vec::with_capacity(out.len());
+        for (i, out) in out.iter_mut().enumerate() {
+            match main_body_period([xx[i], yy[i]]).filter(|_| mandelbrot) {
+                Some(period) => {
+                    *out = Bound::Bounded(Orbit {
+                        period,
+                        ..Orbit::default()
+                    })
+                }
+                // when resuming only bounded pixels continue, escaped ones keep their count
+                None => match out {
+                    Bound::Unbounded(..) if settings.bounds.start > 0 => {}
+                    _ => pending.push(i),
+                },
+            }
+        }
+        for pixels in pending.chunks(step_by) {
+            // the last group is ragged unless the pixels fill it, its missing lanes repeat
+            // the last pixel and are dropped afterwards
+            let lane = |i: usize| pixels[i.min(pixels.len() - 1)];
+            let group_x: Vec<f64> = (0..step_by).map(|i| xx[lane(i)]).collect();
+            let group_y: Vec<f64> = (0..step_by).map(|i| yy[lane(i)]).collect();
+            let mut group: Vec<Bound> = (0..step_by).map(|i| out[lane(i)]).collect();
+            T::check_bounded(&group_x, &group_y, &settings.bounds, &mut group);
+            for (&i, bound) in pixels.iter().zip(group) {
+                out[i] = bound;
+            }
         }
     }
 
     fn compute_row_hp<T: BoundsChecker<Float> + 'static>(
         y: u32,
         start: [&Float; 2],
-        step: &Float,
+        step: [&Float; 2],
         out: &mut [Bound],
         settings: &ComputeSettings,
     ) {
+        let _busy = settings.workers.as_ref().map(|workers| workers.start_row());
         let step_by = T::mask().len();
-        let precision = settings.bounds.precision;
-        let yy = Float::with_val(precision, start[1] + Float::with_val(precision, step * y));
+        let precision = settings.precision;
+        let row = Float::with_val(
+            precision,
+            start[1] + Float::with_val(precision, step[1] * y),
+        );
         for x in (0..settings.width).step_by(step_by) {
             let mut xx: Vec<Float> = Vec::with_capacity(step_by);
+            let mut yy: Vec<Float> = Vec::with_capacity(step_by);
             for i in 0..step_by {
-                xx.push(start[0] + step * Float::with_val(precision, x + i as u32))
+                let (px, py) = settings.transform(
+                    start[0] + step[0] * Float::with_val(precision, x + i as u32),
+                    &row,
+                );
+                xx.push(px);
+                yy.push(py);
             }
-            let yy = vec![Float::with_val(precision, &yy); step_by];
 
             let out = &mut out[x as usize..x as usize + step_by];
-            T::check_bounded(&xx, &yy, &settings.bounds, out);
+            if settings.coordinates {
+                let xx: Vec<f64> = xx.iter().map(Float::to_f64).collect();
+                let yy: Vec<f64> = yy.iter().map(Float::to_f64).collect();
+                Self::store_coordinates(&xx, &yy, out);
+            } else {
+                T::check_bounded(&xx, &yy, &settings.bounds, out);
+            }
+        }
+    }
+
+    /// Fills every row of `mirrored` with the conjugated orbits of its mirror image. With
+    /// `stripes` the stripe averages are flipped along, as the argument of `z` changes sign.
+    fn fill_mirrored(output: &mut [Bound], width: u32, mirrored: &[(u32, u32)], stripes: bool) {
+        let width = width as usize;
+        for &(y, source) in mirrored {
+            let from = source as usize * width;
+            let row: Vec<Bound> = output[from..from + width]
+                .iter()
+                .map(|bound| {
+                    let conjugate = |orbit: &Orbit| Orbit {
+                        z: [orbit.z[0], -orbit.z[1]],
+                        dz: [orbit.dz[0], -orbit.dz[1]],
+                        stripe: if stripes { 1.0 - orbit.stripe } else { 0.0 },
+                        stripe_previous: if stripes {
+                            1.0 - orbit.stripe_previous
+                        } else {
+                            0.0
+                        },
+                        ..*orbit
+                    };
+                    match bound {
+                        Bound::Bounded(orbit) => Bound::Bounded(conjugate(orbit)),
+                        Bound::Unbounded(n, orbit) => Bound::Unbounded(*n, conjugate(orbit)),
+                    }
+                })
+                .collect();
+            let to = y as usize * width;
+            output[to..to + width].copy_from_slice(&row);
+        }
+    }
+
+    fn store_coordinates(xx: &[f64], yy: &[f64], out: &mut [Bound]) {
+        for ((out, &x), &y) in out.iter_mut().zip(xx).zip(yy) {
+            *out = Bound::Unbounded(
+                0,
+                Orbit {
+                    z: [x, y],
+                    ..Orbit::default()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 64;
+
+    fn grid(engine: ComputeEngine) -> ComputedSet {
+        let settings = ComputeSettings::new(
+            Float::with_val(53, -0.5),
+            Float::with_val(53, 0.0),
+            Float::with_val(53, 2.5),
+            SIZE,
+            SIZE,
+            engine,
+            BoundsSettings::new(256, 53),
+        );
+        Compute::compute_set(None, None, &settings).unwrap()
+    }
+
+    fn escape_count(bound: Bound) -> Option<u64> {
+        match bound {
+            Bound::Bounded(_) => None,
+            Bound::Unbounded(n, _) => Some(n),
+        }
+    }
+
+    #[test]
+    fn engines_agree_with_the_reference() {
+        let reference = grid(ComputeEngine::Precision);
+        // pixels on the boundary are chaotic, so a few may diverge from the reference
+        let cases = [
+            (ComputeEngine::Single, 2, 0.95),
+            (ComputeEngine::Double, 1, 0.99),
+            (ComputeEngine::SimdF32x8, 2, 0.95),
+            (ComputeEngine::SimdF32x16, 2, 0.95),
+            (ComputeEngine::SimdF64x4, 1, 0.99),
+            (ComputeEngine::DoubleDouble, 1, 0.99),
+        ];
+        for &(engine, tolerance, fraction) in &cases {
+            let set = grid(engine);
+            let mut matching = 0;
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let expected = escape_count(reference.get(x, y).unwrap());
+                    let actual = escape_count(set.get(x, y).unwrap());
+                    let close = match (expected, actual) {
+                        (None, None) => true,
+                        (Some(a), Some(b)) => (a as i64 - b as i64).abs() <= tolerance,
+                        _ => false,
+                    };
+                    if close {
+                        matching += 1;
+                    }
+                }
+            }
+            let agreement = matching as f64 / (SIZE * SIZE) as f64;
+            assert!(
+                agreement >= fraction,
+                "{:?} agrees with the reference on only {:.1}% of the pixels",
+                engine,
+                agreement * 100.0
+            );
+        }
+    }
+
+    #[test]
+    fn pixels_are_square_in_every_engine() {
+        // only points outside the bailout circle |c| >= 2 escape before the first iteration
+        let (width, height) = (96, 64);
+        for &engine in ComputeEngine::LIST.iter() {
+            let settings = ComputeSettings::new(
+                Float::with_val(53, 0.0),
+                Float::with_val(53, 0.0),
+                Float::with_val(53, 5.0),
+                width,
+                height,
+                engine,
+                BoundsSettings::new(16, 53),
+            );
+            let set = Compute::compute_set(None, None, &settings).unwrap();
+            let inside = |x, y| escape_count(set.get(x, y).unwrap()) != Some(0);
+            // the real and imaginary axes fall exactly on row 32 and column 48
+            let across = (0..width).filter(|&x| inside(x, height / 2)).count();
+            let down = (0..height).filter(|&y| inside(width / 2, y)).count();
+            assert!(
+                (across as i64 - down as i64).abs() <= 1,
+                "{:?} renders the circle {} pixels wide and {} pixels high",
+                engine,
+                across,
+                down
+            );
+            assert!((51..=52).contains(&across), "{:?}: {}", engine, across);
+        }
+    }
+
+    #[test]
+    fn engines_classify_clear_points() {
+        let settings = BoundsSettings::new(256, 53);
+        let interior = [[0.0, 0.0], [-1.0, 0.0], [-0.1, 0.1], [-0.5, 0.3]];
+        let exterior = [[1.0, 1.0], [-2.5, 0.0], [0.5, 0.5], [0.0, 1.5]];
+        for &engine in ComputeEngine::LIST.iter() {
+            for &[x, y] in &interior {
+                let bound = Compute::compute_point(
+                    &Float::with_val(53, x),
+                    &Float::with_val(53, y),
+                    &settings,
+                    engine,
+                );
+                assert!(
+                    escape_count(bound).is_none(),
+                    "{:?} lets {} + {}i escape",
+                    engine,
+                    x,
+                    y
+                );
+            }
+            for &[x, y] in &exterior {
+                let bound = Compute::compute_point(
+                    &Float::with_val(53, x),
+                    &Float::with_val(53, y),
+                    &settings,
+                    engine,
+                );
+                assert!(
+                    escape_count(bound).is_some(),
+                    "{:?} keeps {} + {}i bounded",
+                    engine,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_round_trip() {
+        let float = |value: f64| Float::with_val(128, value);
+        let x = [float(-2.25), float(0.75)];
+        let y = [float(-1.0), float(1.5)];
+        let settings = ComputeSettings::from_bounds(
+            x.clone(),
+            y.clone(),
+            320,
+            100,
+            ComputeEngine::Precision,
+            BoundsSettings::new(16, 128),
+        );
+        let (rect_x, rect_y) = settings.rect();
+        for (expected, actual) in x.iter().chain(&y).zip(rect_x.iter().chain(&rect_y)) {
+            assert!(
+                (expected.to_f64() - actual.to_f64()).abs() < 1e-12,
+                "{} came back as {}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn symmetric_views_mirror_the_upper_half() {
+        let settings = ComputeSettings::new(
+            Float::with_val(53, -0.5),
+            Float::with_val(53, 0.0),
+            Float::with_val(53, 2.5),
+            SIZE,
+            SIZE,
+            ComputeEngine::Double,
+            BoundsSettings::new(256, 53),
+        );
+        let (start, step) = Compute::pixel_grid(&settings);
+        assert!(settings.mirrored_rows(&start[1], &step[1]).is_empty());
+        let settings = settings.with_symmetry(true);
+        assert_eq!(
+            settings.mirrored_rows(&start[1], &step[1]).len(),
+            SIZE as usize / 2 - 1
+        );
+        let set = Compute::compute_set(None, None, &settings).unwrap();
+        let full = grid(ComputeEngine::Double);
+        for y in 1..SIZE / 2 {
+            for x in 0..SIZE {
+                let mirrored = set.get(x, y).unwrap();
+                let source = set.get(x, SIZE - y).unwrap();
+                assert_eq!(escape_count(mirrored), escape_count(source));
+                assert_eq!(mirrored.orbit().z[1], -source.orbit().z[1]);
+                assert_eq!(
+                    escape_count(mirrored),
+                    escape_count(full.get(x, y).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deep_views_exceed_the_precision_of_f64() {
+        let view = |scale: f64, engine| {
+            ComputeSettings::new(
+                Float::with_val(128, -0.75),
+                Float::with_val(128, 0.1),
+                Float::with_val(128, scale),
+                1600,
+                900,
+                engine,
+                BoundsSettings::new(256, 128),
+            )
+        };
+        assert!(!view(2.5, ComputeEngine::Double).exceeds_precision());
+        assert!(!view(1e-12, ComputeEngine::Double).exceeds_precision());
+        assert!(view(1e-14, ComputeEngine::Double).exceeds_precision());
+        assert!(view(1e-14, ComputeEngine::SimdF32x8).exceeds_precision());
+        assert!(!view(1e-14, ComputeEngine::Precision).exceeds_precision());
+        assert!(!view(1e-14, ComputeEngine::DoubleDouble).exceeds_precision());
+        // points placed at 53 bits can't be told apart by iterating them with more
+        assert!(view(1e-14, ComputeEngine::DoubleDouble)
+            .with_precision(53)
+            .exceeds_precision());
+        assert!(view(1e-14, ComputeEngine::Precision)
+            .with_precision(53)
+            .exceeds_precision());
+    }
+
+    #[test]
+    fn distance_estimate_brackets_the_true_distance() {
+        let bounds = BoundsSettings::new(1000, 53)
+            .with_bailout(1e3)
+            .with_distance(true);
+        // the set reaches 0.25 and -2 along the real axis
+        for &(x, distance) in &[(1.0, 0.75), (-2.5, 0.5), (0.5, 0.25)] {
+            let bound = Compute::compute_point(
+                &Float::with_val(53, x),
+                &Float::with_val(53, 0.0),
+                &bounds,
+                ComputeEngine::Double,
+            );
+            let estimate = bound.orbit().distance().unwrap();
+            assert!(
+                estimate / 4.0 <= distance && distance <= estimate * 1.1,
+                "estimated {} at {} for a distance of {}",
+                estimate,
+                x,
+                distance
+            );
+        }
+    }
+
+    #[test]
+    fn extended_sets_match_fresh_ones() {
+        let settings = |engine, limit| {
+            ComputeSettings::new(
+                Float::with_val(53, -0.5),
+                Float::with_val(53, 0.0),
+                Float::with_val(53, 2.5),
+                SIZE,
+                SIZE,
+                engine,
+                BoundsSettings::new(limit, 53).with_stripe_density(5.0),
+            )
+        };
+        for &engine in &[
+            ComputeEngine::Double,
+            ComputeEngine::SimdF32x8,
+            ComputeEngine::SimdF64x4,
+        ] {
+            let previous = Compute::compute_set(None, None, &settings(engine, 256)).unwrap();
+            let extended =
+                Compute::extend_set(None, None, &settings(engine, 1024), &previous, 256).unwrap();
+            let fresh = Compute::compute_set(None, None, &settings(engine, 1024)).unwrap();
+            assert_eq!(extended.get_limit(), 1024);
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let (extended, fresh) = (extended.get(x, y).unwrap(), fresh.get(x, y).unwrap());
+                    assert_eq!(
+                        escape_count(extended),
+                        escape_count(fresh),
+                        "{:?} at ({}, {})",
+                        engine,
+                        x,
+                        y
+                    );
+                    // the stripe sum is rebuilt from the average of the first iterations
+                    let stripes = [extended.orbit().stripe, fresh.orbit().stripe];
+                    assert!(
+                        (stripes[0] - stripes[1]).abs() < 1e-9,
+                        "{:?} stripe average {} instead of {} at ({}, {})",
+                        engine,
+                        stripes[0],
+                        stripes[1],
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rows_crossing_the_main_body_skip_it() {
+        // a single ragged row from outside the set through the bulb and the cardioid
+        let width = 37;
+        let settings = |engine| {
+            ComputeSettings::new(
+                Float::with_val(53, -0.75),
+                Float::with_val(53, 0.1),
+                Float::with_val(53, 2.5 / width as f64),
+                width,
+                1,
+                engine,
+                BoundsSettings::new(256, 53),
+            )
+        };
+        // pixels in the main body are left at the start of their orbit with its period
+        let skipped = |engine| {
+            let set = Compute::compute_set(None, None, &settings(engine)).unwrap();
+            (0..width)
+                .map(|x| match set.get(x, 0).unwrap() {
+                    Bound::Bounded(orbit) if orbit.z == [0.0, 0.0] => orbit.period,
+                    _ => 0,
+                })
+                .collect::<Vec<_>>()
+        };
+        let periods = skipped(ComputeEngine::Double);
+        assert_eq!(skipped(ComputeEngine::SimdF64x4), periods);
+        assert_eq!(skipped(ComputeEngine::SimdF32x16), periods);
+        let skipped = periods.iter().filter(|&&period| period > 0).count() as u32;
+        assert!(periods.iter().all(|&period| period <= 2));
+        assert!(skipped > width / 4, "only {} pixels skipped", skipped);
+    }
+
+    #[test]
+    fn simd_engines_handle_ragged_rows() {
+        let (width, height) = (1601, 3);
+        let settings = |engine| {
+            ComputeSettings::new(
+                Float::with_val(53, 0.5),
+                Float::with_val(53, 0.5),
+                Float::with_val(53, 0.01),
+                width,
+                height,
+                engine,
+                BoundsSettings::new(64, 53),
+            )
+        };
+        let reference = Compute::compute_set(None, None, &settings(ComputeEngine::Double)).unwrap();
+        for &engine in &[
+            ComputeEngine::SimdF32x8,
+            ComputeEngine::SimdF32x16,
+            ComputeEngine::SimdF64x4,
+        ] {
+            let set = Compute::compute_set(None, None, &settings(engine)).unwrap();
+            assert_eq!(set.get_size(), (width, height));
+            // the last pixels fall in the ragged group of every lane count
+            for y in 0..height {
+                for x in width - 16..width {
+                    let expected = escape_count(reference.get(x, y).unwrap());
+                    let actual = escape_count(set.get(x, y).unwrap());
+                    let close = match (expected, actual) {
+                        (Some(a), Some(b)) => (a as i64 - b as i64).abs() <= 2,
+                        (a, b) => a == b,
+                    };
+                    assert!(
+                        close,
+                        "{:?} computed {:?} instead of {:?}",
+                        engine, actual, expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threaded_and_serial_sets_are_identical() {
+        let mut thread_pool = ThreadPool::new(4);
+        for &engine in ComputeEngine::LIST.iter() {
+            let settings = ComputeSettings::new(
+                Float::with_val(53, -0.5),
+                Float::with_val(53, 0.0),
+                Float::with_val(53, 2.5),
+                SIZE,
+                SIZE,
+                engine,
+                BoundsSettings::new(256, 53),
+            );
+            let serial = Compute::compute_set(None, None, &settings).unwrap();
+            let threaded = Compute::compute_set(Some(&mut thread_pool), None, &settings).unwrap();
+            // the debug output holds every field of every orbit at full precision
+            let bounds = |set: &ComputedSet| format!("{:?}", set.iter().unwrap().as_slice());
+            assert!(
+                bounds(&serial) == bounds(&threaded),
+                "{:?} computes a different set on the thread pool",
+                engine
+            );
+        }
+    }
+
+    #[test]
+    fn saved_sets_load_unchanged() {
+        let settings = ComputeSettings::new(
+            Float::with_val(53, -0.5),
+            Float::with_val(53, 0.0),
+            Float::with_val(53, 2.5),
+            SIZE,
+            SIZE / 2,
+            ComputeEngine::Double,
+            BoundsSettings::new(256, 53)
+                .with_bailout(16.0)
+                .with_stripe_density(3.0)
+                .with_distance(true),
+        );
+        let set = Compute::compute_set(None, None, &settings).unwrap();
+        let mut bytes = Vec::new();
+        set.save(&mut bytes).unwrap();
+        let loaded = ComputedSet::load(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.get_size(), set.get_size());
+        assert_eq!(loaded.get_limit(), set.get_limit());
+        assert_eq!(loaded.get_bailout(), set.get_bailout());
+        // the debug output holds every field of every orbit at full precision
+        let bounds = |set: &ComputedSet| format!("{:?}", set.iter().unwrap().as_slice());
+        assert!(bounds(&loaded) == bounds(&set), "the orbits changed");
+        for y in 0..SIZE / 2 {
+            for x in 0..SIZE {
+                assert_eq!(
+                    escape_count(loaded.get(x, y).unwrap()),
+                    escape_count(set.get(x, y).unwrap())
+                );
+            }
+        }
+        // cutting the file short anywhere is an error, not a panic
+        for &length in &[3, 20, 40, bytes.len() - 1] {
+            assert!(ComputedSet::load(&mut &bytes[..length]).is_err());
         }
     }
 }
@@ -0,0 +1,10 @@
+/// Callback receiving the progress of a computation. Returning `false` aborts the computation
+/// once nobody is waiting for its result anymore.
+pub type Progress<'a> = Option<&'a dyn Fn(ComputeEvent) -> bool>;
+
+#[derive(Clone)]
+pub enum ComputeEvent {
+    Start,
+    End,
+    Progress((u32, u32)),
+}
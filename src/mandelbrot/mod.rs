@@ -1,2 +1,7 @@
+#[cfg(test)]
+mod benches;
 pub mod bounded;
 pub mod compute;
+pub mod double_double;
+pub mod events;
+pub mod timing;
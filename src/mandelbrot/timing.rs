@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "row-timing")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "row-timing")]
+use std::time::Instant;
+
+/// Collects how long each row took to compute, for spotting stragglers that keep the
+/// threadpool waiting. Without the `row-timing` feature this is empty and does nothing.
+#[derive(Clone, Default)]
+pub struct RowTimings {
+    #[cfg(feature = "row-timing")]
+    rows: Arc<Mutex<Vec<f64>>>,
+}
+
+impl RowTimings {
+    pub fn time<R, F: FnOnce() -> R>(&self, row: F) -> R {
+        #[cfg(feature = "row-timing")]
+        {
+            let start = Instant::now();
+            let result = row();
+            let elapsed = start.elapsed().as_secs_f64();
+            self.rows.lock().unwrap().push(elapsed);
+            result
+        }
+        #[cfg(not(feature = "row-timing"))]
+        row()
+    }
+
+    /// Prints the min/max/mean/stddev of the row times recorded so far.
+    pub fn report(&self) {
+        #[cfg(feature = "row-timing")]
+        {
+            let rows = self.rows.lock().unwrap();
+            if rows.is_empty() {
+                return;
+            }
+            let n = rows.len() as f64;
+            let min = rows.iter().cloned().fold(std::f64::INFINITY, f64::min);
+            let max = rows.iter().cloned().fold(0.0, f64::max);
+            let mean = rows.iter().sum::<f64>() / n;
+            let variance = rows.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / n;
+            eprintln!(
+                "row times over {} rows: min {:.6}s max {:.6}s mean {:.6}s stddev {:.6}s",
+                rows.len(),
+                min,
+                max,
+                mean,
+                variance.sqrt()
+            );
+        }
+    }
+}
+
+/// Live counts of the workers computing a set, for showing how well the threadpool is used.
+#[derive(Default)]
+pub struct WorkerStats {
+    busy: AtomicUsize,
+    rows: AtomicUsize,
+}
+
+impl WorkerStats {
+    /// Workers computing a row right now.
+    pub fn busy(&self) -> usize {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// Rows finished so far.
+    pub fn rows(&self) -> usize {
+        self.rows.load(Ordering::Relaxed)
+    }
+
+    /// Counts the calling worker as busy until the guard is dropped, which finishes the row.
+    pub fn start_row(&self) -> RowGuard {
+        self.busy.fetch_add(1, Ordering::Relaxed);
+        RowGuard(self)
+    }
+}
+
+pub struct RowGuard<'a>(&'a WorkerStats);
+
+impl Drop for RowGuard<'_> {
+    fn drop(&mut self) {
+        self.0.busy.fetch_sub(1, Ordering::Relaxed);
+        self.0.rows.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -1,4 +1,10 @@
-use std::sync::mpsc::{channel, Sender};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, Sender},
+    Arc,
+};
 use std::thread;
 
 use rug::Float;
@@ -7,46 +13,240 @@ use threadpool::ThreadPool;
 use glium::{
     glutin::{
         self,
-        event::{ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta, WindowEvent},
+        event::{
+            ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+            VirtualKeyCode, WindowEvent,
+        },
         event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
     },
     Surface,
 };
-use imgui::{Condition, Context, FontConfig, FontGlyphRanges, FontSource};
+use imgui::{Condition, Context, FontConfig, FontGlyphRanges, FontSource, ImString, StyleColor};
 use imgui_winit_support::{HiDpiMode, WinitPlatform};
 
 use num_traits::{FromPrimitive, ToPrimitive};
 
+use crate::export::{copy_image, RenderSettings, View};
 use crate::mandelbrot::{
-    bounded::BoundsSettings,
-    compute::{Compute, ComputeEngine, ComputeSettings, ComputedSet},
+    bounded::{orbit_path, Bound, BoundsSettings, Fractal},
+    compute::{subpixel_offset, Compute, ComputeEngine, ComputeSettings, ComputedSet},
+    events::ComputeEvent,
+    timing::WorkerStats,
+};
+
+use crate::ui::{
+    color::{ColorMode, ColorSettings, TransferCurve},
+    gradient::load_gradient,
+    render::{view_matrix, view_position, AppRenderer},
 };
 
-use crate::ui::{events::ComputeEvent, render::AppRenderer};
+const COMPUTED_SET_FILE: &str = "mandelbrot.mbcs";
+const WINDOW_STATE_FILE: &str = "mandelbrot.window";
+const PALETTE_FILE: &str = "mandelbrot.palette";
+/// Locations queued by the "Queue job" button, one per line, for the `batch` subcommand.
+const JOBS_FILE: &str = "mandelbrot.jobs";
+/// Image whose middle row is loaded as palette by the "Image palette" button.
+const PALETTE_IMAGE_FILE: &str = "mandelbrot.palette.png";
+const HISTOGRAM_BINS: usize = 100;
+/// Smallest drag, as a fraction of the window along both axes, that zooms in on a box.
+const DRAG_THRESHOLD: f64 = 0.001;
+/// Most progress updates a compute sends, a tall render would otherwise send one per row.
+const PROGRESS_STEPS: u32 = 100;
+/// Iterations added or removed by the +/- keys, ten times as many with shift.
+const ITERATION_STEP: u64 = 100;
+/// View heights the arrow keys pan by, with shift they pan by a single pixel instead.
+const PAN_STEP: f64 = 0.1;
+/// Colors shown in the legend strip, and its labelled escape counts.
+const LEGEND_STEPS: usize = 64;
+const LEGEND_TICKS: u64 = 4;
+/// Subsamples blended into the view by the progressive anti-aliasing, the first being the set.
+const MAX_SAMPLES: u32 = 16;
+/// Time without input after which the idle quality pass starts anti-aliasing the view.
+const IDLE_QUALITY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+/// Largest factor the resolution is divided by to stay within the compute budget.
+const MAX_DRAFT_DIVISOR: u32 = 8;
+/// Time a reduced resolution view has to stay untouched before it is rendered in full.
+const DRAFT_IDLE: std::time::Duration = std::time::Duration::from_millis(300);
+/// Resolution of the live preview of a zoom selection.
+const PREVIEW_SIZE: [u32; 2] = [240, 135];
+/// Precision above which the UI warns about slow renders.
+const PRECISION_WARNING: u32 = 512;
+/// Largest precision that can be entered, far beyond anything a render finishes with.
+const MAX_PRECISION: u32 = 8192;
+/// Threads of the pool every compute runs on.
+const WORKER_THREADS: usize = 8;
+/// Longest orbit drawn, index buffers are limited to u16.
+const ORBIT_POINTS: usize = 10_000;
+
+/// Label, engine, precision and iterations of the one-click setting presets.
+const PRESETS: [(&str, ComputeEngine, u32, u64); 4] = [
+    (
+        "Fast (SimdF64x4, 53-bit)",
+        ComputeEngine::SimdF64x4,
+        53,
+        250,
+    ),
+    (
+        "Quality (SimdF64x4, 1000 iters)",
+        ComputeEngine::SimdF64x4,
+        53,
+        1000,
+    ),
+    (
+        "Extended (DoubleDouble, 106-bit)",
+        ComputeEngine::DoubleDouble,
+        106,
+        2000,
+    ),
+    (
+        "Deep (Precision, 128-bit)",
+        ComputeEngine::Precision,
+        128,
+        2000,
+    ),
+];
+
+/// Label, center x and y, scale and iterations of well known places to jump to.
+const LOCATIONS: [(&str, &str, &str, &str, u64); 3] = [
+    ("Seahorse valley", "-0.7453", "0.1127", "0.0065", 1000),
+    ("Elephant valley", "0.2850", "0.0100", "0.0200", 1000),
+    ("Triple spiral valley", "-0.0880", "0.6540", "0.0200", 1500),
+];
 
 #[derive(Clone)]
 pub struct AppSettings {
+    /// Bits of the view coordinates and the pixel positions.
     precision: u32,
+    /// Bits the Precision engine iterates with, 0 for the same as `precision`.
+    iteration_precision: u32,
     resolution: [u32; 2],
     iterations: u64,
+    bailout: f32,
+    /// Frequency of the stripe average coloring, 0 to not compute it.
+    stripe_density: f32,
+    /// Renders the density of escaping orbits from this many random points instead of the set.
+    buddhabrot: bool,
+    buddhabrot_samples: u32,
     engine: ComputeEngine,
+    /// Also computes the view with `compare_engine` and shows it on the right half.
+    pub compare: bool,
+    compare_engine: ComputeEngine,
+    pub fractal: Fractal,
+    invert: bool,
+    /// Unrolls the view around its center into log distance by angle.
+    log_projection: bool,
+    /// Counterclockwise rotation of the view in degrees.
+    angle: f32,
+    pub color: ColorSettings,
+    free_aspect: bool,
+    pub show_crosshair: bool,
+    /// Draws the real and imaginary axes with ticks at the integers.
+    pub show_axes: bool,
+    pub selection_color: [f32; 3],
+    /// Opacity of the darkening outside a drag selection, 0 to leave it as is.
+    pub selection_dim: f32,
+    pub show_minimap: bool,
+    /// Interpolates between the pixels of the set when drawing it scaled, instead of showing
+    /// each as a block.
+    pub smooth_sampling: bool,
+    /// Shows how many workers are busy and how fast rows complete while computing.
+    show_workers: bool,
+    pub lock_aspect: bool,
+    accumulate: bool,
+    /// Anti-aliases the view as with `accumulate` once there was no input for a while.
+    idle_quality: bool,
+    jitter_seed: u64,
+    drag_preview: bool,
+    /// Computes rows from the middle of the image outward.
+    center_out: bool,
+    /// Mirrors the rows on one side of the real axis instead of computing them.
+    symmetry: bool,
+    /// Computes on the UI's compute thread alone, for output that can be reproduced exactly.
+    single_threaded: bool,
+    /// Waits for the display refresh between frames, only read when the window opens.
+    pub vsync: bool,
+    /// Milliseconds a render may take before the following ones drop resolution, 0 for no limit.
+    compute_budget: u32,
 }
 
 impl AppSettings {
     pub fn new() -> AppSettings {
         AppSettings {
             precision: 53,
+            iteration_precision: 0,
             resolution: [1600, 900],
             iterations: 1000,
+            bailout: 2.0,
+            stripe_density: 0.0,
+            buddhabrot: false,
+            buddhabrot_samples: 1_000_000,
             engine: ComputeEngine::SimdF64x4,
+            compare: false,
+            compare_engine: ComputeEngine::Precision,
+            fractal: Fractal::Mandelbrot,
+            invert: false,
+            log_projection: false,
+            angle: 0.0,
+            color: ColorSettings::new(),
+            free_aspect: false,
+            show_crosshair: false,
+            show_axes: false,
+            selection_color: [1.0, 1.0, 1.0],
+            selection_dim: 0.5,
+            show_minimap: false,
+            smooth_sampling: true,
+            show_workers: false,
+            lock_aspect: false,
+            accumulate: false,
+            idle_quality: false,
+            jitter_seed: 0,
+            drag_preview: false,
+            center_out: false,
+            symmetry: false,
+            single_threaded: false,
+            compute_budget: 0,
+            vsync: true,
+        }
+    }
+
+    /// Bits the orbits are iterated with.
+    fn iteration_precision(&self) -> u32 {
+        match self.iteration_precision {
+            0 => self.precision,
+            bits => bits,
         }
     }
+
+    /// Whether the orbits need their derivative for the distance coloring.
+    fn distance(&self) -> bool {
+        matches!(self.color.mode, ColorMode::Distance)
+    }
+
+    /// Whether sets computed with both settings cover the same pixels with the same formula.
+    fn same_view(&self, other: &AppSettings) -> bool {
+        self.precision == other.precision
+            && self.iteration_precision == other.iteration_precision
+            && self.resolution == other.resolution
+            && self.engine == other.engine
+            && self.fractal == other.fractal
+            && self.bailout == other.bailout
+            && self.invert == other.invert
+            && self.log_projection == other.log_projection
+            && self.angle == other.angle
+            && self.stripe_density == other.stripe_density
+            && self.buddhabrot == other.buddhabrot
+            && self.buddhabrot_samples == other.buddhabrot_samples
+            && self.color.coordinates == other.color.coordinates
+            && self.distance() == other.distance()
+            && self.color.color_period == other.color.color_period
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct ZoomState {
     pos: [Float; 2],
     scale: Float,
+    stretch: f64,
 }
 
 impl ZoomState {
@@ -57,9 +257,40 @@ impl ZoomState {
                 Float::with_val(settings.precision, 0.0),
             ],
             scale: Float::with_val(settings.precision, 1.75),
+            stretch: 1.0,
+        }
+    }
+
+    /// A view of `scale` high centered on the decimal coordinates `x` and `y`.
+    fn at(x: &str, y: &str, scale: &str, settings: &AppSettings) -> ZoomState {
+        let float = |value: &str| Float::with_val(settings.precision, Float::parse(value).unwrap());
+        ZoomState {
+            pos: [float(x), float(y)],
+            scale: float(scale),
+            stretch: 1.0,
         }
     }
 
+    /// Moves to the center `x`, `y` with height `scale`, all decimals that may use scientific
+    /// notation. Returns which of them failed to parse, the view only changes if none did.
+    fn set_coordinates(&mut self, values: [&str; 3], settings: &AppSettings) -> [bool; 3] {
+        let parse = |value: &str| {
+            Float::parse(value.trim())
+                .ok()
+                .map(|value| Float::with_val(settings.precision, value))
+                // "inf" and "nan" parse as well but describe no view
+                .filter(|value| value.is_finite())
+        };
+        let [x, y, scale] = [parse(values[0]), parse(values[1]), parse(values[2])];
+        let scale = scale.filter(|scale| *scale > 0);
+        let invalid = [x.is_none(), y.is_none(), scale.is_none()];
+        if let (Some(x), Some(y), Some(scale)) = (x, y, scale) {
+            self.pos = [x, y];
+            self.scale = scale;
+        }
+        invalid
+    }
+
     fn get_x(&self) -> &Float {
         &self.pos[0]
     }
@@ -72,83 +303,509 @@ impl ZoomState {
         &self.scale
     }
 
+    fn get_stretch(&self) -> f64 {
+        self.stretch
+    }
+
+    fn ratio(&self, settings: &AppSettings) -> f64 {
+        settings.resolution[0] as f64 / settings.resolution[1] as f64 * self.stretch
+    }
+
+    /// Moves the center by `offset` view heights along the (rotated) screen axes, y up.
+    fn shift(&mut self, offset: [f64; 2], settings: &AppSettings) {
+        let [dx, dy] = rotate(offset, settings.angle);
+        self.pos = [
+            &self.pos[0] + Float::with_val(settings.precision, dx * self.scale.clone()),
+            &self.pos[1] + Float::with_val(settings.precision, dy * self.scale.clone()),
+        ];
+    }
+
+    /// Moves the center by `pixels` pixels of the computed set along the screen axes, y up.
+    fn pan_pixels(&mut self, pixels: [f64; 2], settings: &AppSettings) {
+        // pixels are square, as wide as the view height over the vertical resolution
+        let pixel = 1.0 / settings.resolution[1] as f64;
+        self.shift(
+            [pixels[0] * pixel * self.stretch, pixels[1] * pixel],
+            settings,
+        );
+    }
+
     fn set_by_dragging(&mut self, start: [f64; 2], end: [f64; 2], settings: &AppSettings) {
         let scale_xy = [(start[0] - end[0]).abs(), (start[1] - end[1]).abs()];
-        let ratio = Float::with_val(settings.precision, settings.resolution[0])
-            / f64::from(settings.resolution[1]);
+        let ratio = self.ratio(settings);
         let scale = Float::with_val(settings.precision, scale_xy[1]) * &self.scale;
-        let pos = [
-            &self.pos[0]
-                + Float::with_val(
-                    settings.precision,
-                    ((start[0] + end[0]) - 1.0) / 2.0 * self.scale.clone() * ratio,
-                ),
-            &self.pos[1]
-                - Float::with_val(
-                    settings.precision,
-                    ((start[1] + end[1]) - 1.0) / 2.0 * self.scale.clone(),
-                ),
-        ];
-        self.pos = pos;
+        self.shift(
+            [
+                ((start[0] + end[0]) - 1.0) / 2.0 * ratio,
+                (1.0 - (start[1] + end[1])) / 2.0,
+            ],
+            settings,
+        );
         self.scale = scale;
+        if settings.free_aspect && scale_xy[1] > 0.0 {
+            self.stretch *= scale_xy[0] / scale_xy[1];
+        }
+    }
+
+    /// Zooms in on the box dragged from `start` to `end` in any direction, or by
+    /// `click_scale` on `start` when the mouse barely moved along either axis.
+    fn finish_drag(
+        &mut self,
+        start: [f64; 2],
+        end: [f64; 2],
+        click_scale: f64,
+        settings: &AppSettings,
+    ) {
+        if (end[0] - start[0]).abs() > DRAG_THRESHOLD && (end[1] - start[1]).abs() > DRAG_THRESHOLD
+        {
+            self.set_by_dragging(start, end, settings);
+        } else {
+            self.zoom_position(start, click_scale, settings);
+        }
     }
 
     fn zoom_position(&mut self, pos: [f64; 2], scale: f64, settings: &AppSettings) {
         self.scale *= scale;
-        let ratio = Float::with_val(settings.precision, settings.resolution[0])
-            / f64::from(settings.resolution[1]);
-        let pos = [
-            &self.pos[0]
-                + Float::with_val(
-                    settings.precision,
-                    (pos[0] - 0.5) * self.scale.clone() * ratio,
-                ),
-            &self.pos[1] - Float::with_val(settings.precision, (pos[1] - 0.5) * self.scale.clone()),
-        ];
-        self.pos = pos;
+        let ratio = self.ratio(settings);
+        self.shift([(pos[0] - 0.5) * ratio, 0.5 - pos[1]], settings);
     }
 
     fn zoom_scale(&mut self, scale: f64) {
         self.scale *= scale
     }
+
+    /// One-line description of the view and the settings needed to reproduce it.
+    pub fn location(&self, settings: &AppSettings) -> String {
+        format!(
+            "x={} y={} scale={} stretch={} iterations={} precision={} engine={:?} fractal={:?} \
+             invert={} bailout={} angle={}",
+            self.pos[0].to_string_radix(10, None),
+            self.pos[1].to_string_radix(10, None),
+            self.scale.to_string_radix(10, None),
+            self.stretch,
+            settings.iterations,
+            settings.precision,
+            settings.engine,
+            settings.fractal,
+            settings.invert,
+            settings.bailout,
+            settings.angle,
+        )
+    }
+
+    /// Command line for the `render` subcommand that reproduces this view headless.
+    pub fn render_command(&self, settings: &AppSettings) -> String {
+        format!(
+            "mandelbrot-rust render --x {} --y {} --scale {} --width {} --height {} \
+             --iterations {} --precision {} --engine {:?} --fractal {:?} --bailout {} \
+             --out out.png",
+            self.pos[0].to_string_radix(10, None),
+            self.pos[1].to_string_radix(10, None),
+            self.scale.to_string_radix(10, None),
+            settings.resolution[0],
+            settings.resolution[1],
+            settings.iterations,
+            settings.precision,
+            settings.engine,
+            settings.fractal,
+            settings.bailout,
+        )
+    }
+
+    /// Parses a string made by `location`, returning the view and `settings` updated to match.
+    pub fn parse_location(
+        location: &str,
+        settings: &AppSettings,
+    ) -> Result<(ZoomState, AppSettings), String> {
+        let mut values = HashMap::new();
+        for part in location.split_whitespace() {
+            let mut pair = part.splitn(2, '=');
+            match (pair.next(), pair.next()) {
+                (Some(key), Some(value)) => values.insert(key, value),
+                _ => return Err(format!("expected key=value, got '{}'", part)),
+            };
+        }
+
+        let mut settings = settings.clone();
+        if let Some(value) = values.get("precision") {
+            settings.precision = value
+                .parse()
+                .map_err(|e| format!("invalid precision '{}': {}", value, e))?;
+            if settings.precision == 0 || settings.precision > MAX_PRECISION {
+                return Err(format!("precision must be 1 to {} bits", MAX_PRECISION));
+            }
+        }
+        if let Some(value) = values.get("iterations") {
+            settings.iterations = value
+                .parse()
+                .map_err(|e| format!("invalid iterations '{}': {}", value, e))?;
+        }
+        if let Some(value) = values.get("engine") {
+            settings.engine = ComputeEngine::from_name(value)
+                .ok_or_else(|| format!("unknown engine '{}'", value))?;
+        }
+        if let Some(value) = values.get("fractal") {
+            settings.fractal =
+                Fractal::from_name(value).ok_or_else(|| format!("unknown fractal '{}'", value))?;
+        }
+        if let Some(value) = values.get("bailout") {
+            settings.bailout = value
+                .parse()
+                .map_err(|e| format!("invalid bailout '{}': {}", value, e))?;
+        }
+        if let Some(value) = values.get("angle") {
+            settings.angle = value
+                .parse()
+                .map_err(|e| format!("invalid angle '{}': {}", value, e))?;
+        }
+        if let Some(value) = values.get("invert") {
+            settings.invert = value
+                .parse()
+                .map_err(|e| format!("invalid invert '{}': {}", value, e))?;
+        }
+
+        let float = |key: &str| -> Result<Float, String> {
+            let value = values
+                .get(key)
+                .ok_or_else(|| format!("missing '{}'", key))?;
+            Float::parse(value)
+                .map(|f| Float::with_val(settings.precision, f))
+                .map_err(|e| format!("invalid {} '{}': {}", key, value, e))
+        };
+        let stretch = match values.get("stretch") {
+            Some(value) => value
+                .parse()
+                .map_err(|e| format!("invalid stretch '{}': {}", value, e))?,
+            None => 1.0,
+        };
+        let zoom = ZoomState {
+            pos: [float("x")?, float("y")?],
+            scale: float("scale")?,
+            stretch,
+        };
+        Ok((zoom, settings))
+    }
+
+    /// Whether neighbouring pixels of this view lie closer together than the engine of
+    /// `settings` can tell apart.
+    pub fn exceeds_precision(&self, settings: &AppSettings) -> bool {
+        let [w, h] = settings.resolution;
+        ComputeSettings::new(
+            self.pos[0].clone(),
+            self.pos[1].clone(),
+            self.scale.clone(),
+            w,
+            h,
+            settings.engine,
+            BoundsSettings::new(settings.iterations, settings.iteration_precision()),
+        )
+        .with_stretch(self.stretch)
+        .with_precision(settings.precision)
+        .exceeds_precision()
+    }
+
+    /// Headless render of this view with `settings`, as queued for the `batch` subcommand.
+    pub fn render_job(&self, settings: &AppSettings) -> (View, RenderSettings) {
+        let view = View::new(self.pos[0].clone(), self.pos[1].clone(), self.scale.clone());
+        let render = RenderSettings {
+            size: settings.resolution,
+            engine: settings.engine,
+            bounds: BoundsSettings::new(settings.iterations, settings.iteration_precision())
+                .with_fractal(settings.fractal)
+                .with_bailout(settings.bailout as f64)
+                .with_distance(settings.distance()),
+            color: settings.color.clone(),
+            depth: png::BitDepth::Eight,
+            dpi: None,
+        };
+        (view, render)
+    }
+
+    /// Lower left and upper right corner of the area shown with `settings`, in f64.
+    pub fn view_rect(&self, settings: &AppSettings) -> [[f64; 2]; 2] {
+        let ratio = settings.resolution[0] as f64 / settings.resolution[1] as f64 * self.stretch;
+        let (x, y, scale) = (
+            self.pos[0].to_f64(),
+            self.pos[1].to_f64(),
+            self.scale.to_f64(),
+        );
+        [
+            [x - scale * ratio / 2.0, y - scale / 2.0],
+            [x + scale * ratio / 2.0, y + scale / 2.0],
+        ]
+    }
+
+    /// Point in the plane under the normalized (0..1, top left origin) window position `pos`.
+    pub fn point_at(&self, pos: [f64; 2], settings: &AppSettings) -> [f64; 2] {
+        let scale = self.scale.to_f64();
+        let [dx, dy] = rotate(
+            [(pos[0] - 0.5) * self.ratio(settings), 0.5 - pos[1]],
+            settings.angle,
+        );
+        [
+            self.pos[0].to_f64() + dx * scale,
+            self.pos[1].to_f64() + dy * scale,
+        ]
+    }
+
+    /// Screen-space (-1..1) position of the point `z` in the plane, the inverse of `point_at`.
+    pub fn screen_position(&self, z: [f64; 2], settings: &AppSettings) -> [f32; 2] {
+        let scale = self.scale.to_f64();
+        let [u, v] = rotate(
+            [
+                (z[0] - self.pos[0].to_f64()) / scale,
+                (z[1] - self.pos[1].to_f64()) / scale,
+            ],
+            -settings.angle,
+        );
+        [(u / self.ratio(settings) * 2.0) as f32, (v * 2.0) as f32]
+    }
+
+    /// Screen-space (-1..1) position of this state's center inside the rendered `view`.
+    pub fn screen_offset(&self, view: &ZoomState, ratio: f64) -> [f32; 2] {
+        let dx = Float::with_val(self.pos[0].prec(), &self.pos[0] - &view.pos[0]) / &view.scale;
+        let dy = Float::with_val(self.pos[1].prec(), &self.pos[1] - &view.pos[1]) / &view.scale;
+        [
+            (dx.to_f64() / (ratio * view.stretch) * 2.0) as f32,
+            (dy.to_f64() * 2.0) as f32,
+        ]
+    }
+}
+
+/// Window placement remembered between runs, in physical pixels.
+struct WindowState {
+    position: [i32; 2],
+    size: [u32; 2],
+}
+
+impl WindowState {
+    fn of(window: &glutin::window::Window) -> Option<WindowState> {
+        let position = window.outer_position().ok()?;
+        let size = window.inner_size();
+        Some(WindowState {
+            position: [position.x, position.y],
+            size: [size.width, size.height],
+        })
+    }
+
+    fn load() -> Option<WindowState> {
+        let text = std::fs::read_to_string(WINDOW_STATE_FILE).ok()?;
+        let mut values = text.split_whitespace();
+        let mut next = || values.next()?.parse::<i64>().ok();
+        let (x, y, width, height) = (next()?, next()?, next()?, next()?);
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        Some(WindowState {
+            position: [x as i32, y as i32],
+            size: [width as u32, height as u32],
+        })
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        std::fs::write(
+            WINDOW_STATE_FILE,
+            format!(
+                "{} {} {} {}\n",
+                self.position[0], self.position[1], self.size[0], self.size[1]
+            ),
+        )
+    }
+}
+
+/// Rotates `offset` counterclockwise by `degrees`.
+fn rotate(offset: [f64; 2], degrees: f32) -> [f64; 2] {
+    let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+    [
+        offset[0] * cos - offset[1] * sin,
+        offset[0] * sin + offset[1] * cos,
+    ]
 }
 
 pub struct AppState {
+    /// Latest finished set, kept on screen while the next one computes and only replaced
+    /// once it arrives, so raising the precision never blanks the view.
     pub computed_set: ComputedSet,
     pub set_valid: bool,
+    /// Jittered copies of `computed_set` waiting to be blended in, and the number blended
+    /// or underway including the set itself.
+    pub pending_samples: Vec<ComputedSet>,
+    pub samples: u32,
+    /// The view computed with the comparison engine, once it arrives.
+    pub compare_set: Option<ComputedSet>,
+    pub compare_valid: bool,
+    sample_busy: bool,
+    sample_cancel: Arc<AtomicBool>,
+    pub histogram: Vec<f32>,
     pub progress: ComputeEvent,
 
     pub mouse_pos: [f64; 2],
     pub dragging: bool,
+    /// Low resolution render of what the current selection would zoom to.
+    pub preview: Option<ComputedSet>,
+    pub preview_valid: bool,
+    preview_zoom: Option<ZoomState>,
+    preview_busy: bool,
+    pub orbit: Vec<[f64; 2]>,
     pub mouse_start: [f64; 2],
     pub mouse_end: [f64; 2],
     pub modifiers: ModifiersState,
     pub zoomstate: ZoomState,
+    pub compute_zoom: Option<ZoomState>,
+    pub view_zoom: Option<ZoomState>,
+    pub compute_settings: Option<AppSettings>,
+    pub view_settings: Option<AppSettings>,
     pub compute_valid: bool,
+    /// Cleared to hold off recomputing while settings are being changed.
+    pub compute_enabled: bool,
     pub compute_busy: bool,
+    pub compute_cancel: Arc<AtomicBool>,
+    /// Counts launched renders, only the set of the latest one is shown.
+    compute_generation: u64,
+    /// Resolution being edited in the UI, applied with the "Apply size" button.
+    resolution_input: [i32; 2],
+    /// Center and height being typed in, and which of them did not parse.
+    coordinate_input: [ImString; 3],
+    coordinate_invalid: [bool; 3],
 
     pub compute_start: Option<std::time::Instant>,
+    /// Counters of the running compute, read by the worker overlay.
+    workers: Arc<WorkerStats>,
     pub compute_time: Option<std::time::Duration>,
+    /// Resolution divisor for interactive renders, adapted to the compute budget.
+    draft_divisor: u32,
+    /// Whether the running compute is a full resolution render of a reduced one.
+    refining: bool,
+    /// When the current reduced resolution view arrived, if it is one.
+    draft_since: Option<std::time::Instant>,
+    /// When the last key, mouse button, wheel or cursor movement arrived.
+    last_input: std::time::Instant,
+    /// Whether settings edits are staged until Render instead of applied live.
+    manual_apply: bool,
+    /// Copy of the settings edited by the ui while in manual apply mode.
+    staged_settings: Option<AppSettings>,
+    /// Set by Render to copy the staged settings to the live ones.
+    apply_staged: bool,
+    /// Set by the ui when it moved the view or replaced the shown set, which take effect right
+    /// away even while settings edits are staged.
+    view_replaced: bool,
 }
 
 impl AppState {
     fn new(settings: &AppSettings) -> AppState {
         AppState {
-            computed_set: ComputedSet::empty(64, 64),
+            computed_set: ComputedSet::empty(settings.resolution[0], settings.resolution[1]),
             set_valid: false,
+            pending_samples: Vec::new(),
+            samples: 1,
+            compare_set: None,
+            compare_valid: false,
+            sample_busy: false,
+            sample_cancel: Arc::new(AtomicBool::new(false)),
+            histogram: Vec::new(),
             progress: ComputeEvent::End,
 
             mouse_pos: [0.0, 0.0],
             dragging: false,
+            preview: None,
+            preview_valid: false,
+            preview_zoom: None,
+            preview_busy: false,
+            orbit: Vec::new(),
             mouse_start: [0.0, 0.0],
             mouse_end: [0.0, 0.0],
             modifiers: ModifiersState::empty(),
             zoomstate: ZoomState::new(settings),
+            compute_zoom: None,
+            view_zoom: None,
+            compute_settings: None,
+            view_settings: None,
             compute_valid: false,
+            compute_enabled: true,
             compute_busy: false,
+            compute_cancel: Arc::new(AtomicBool::new(false)),
+            compute_generation: 0,
+            resolution_input: [settings.resolution[0] as i32, settings.resolution[1] as i32],
+            coordinate_input: [
+                ImString::with_capacity(256),
+                ImString::with_capacity(256),
+                ImString::with_capacity(256),
+            ],
+            coordinate_invalid: [false; 3],
 
             compute_start: None,
+            workers: Arc::new(WorkerStats::default()),
             compute_time: None,
+            draft_divisor: 1,
+            refining: false,
+            draft_since: None,
+            last_input: std::time::Instant::now(),
+            manual_apply: false,
+            staged_settings: None,
+            apply_staged: false,
+            view_replaced: false,
+        }
+    }
+
+    /// Moves the view to `zoom` and computes it, also while settings edits are staged.
+    fn move_view(&mut self, zoom: ZoomState) {
+        self.zoomstate = zoom;
+        self.compute_valid = false;
+        self.view_replaced = true;
+    }
+
+    /// Shows a set loaded from a file, which belongs to no view until the next render.
+    fn show_loaded_set(&mut self, set: ComputedSet) {
+        self.computed_set = set;
+        self.set_valid = false;
+        self.view_zoom = None;
+        self.view_settings = None;
+        self.view_replaced = true;
+    }
+
+    /// Builds the ui with `build`. In manual apply mode it edits the staged copy of the
+    /// settings and the recomputes those edits ask for wait for Render, moving the view or
+    /// loading a set takes effect right away.
+    fn build_staged<F>(&mut self, settings: &mut AppSettings, build: F)
+    where
+        F: FnOnce(&mut AppState, &mut AppSettings),
+    {
+        self.view_replaced = false;
+        match self.staged_settings.take() {
+            Some(mut staged) => {
+                let (compute_valid, set_valid) = (self.compute_valid, self.set_valid);
+                build(self, &mut staged);
+                if self.apply_staged || !self.manual_apply {
+                    *settings = staged.clone();
+                    self.apply_staged = false;
+                    self.compute_valid = false;
+                    self.set_valid = false;
+                } else if !self.view_replaced {
+                    self.compute_valid = compute_valid;
+                    self.set_valid = set_valid;
+                }
+                if self.manual_apply {
+                    self.staged_settings = Some(staged);
+                }
+            }
+            None => {
+                build(self, settings);
+                self.apply_staged = false;
+                if self.manual_apply {
+                    self.staged_settings = Some(settings.clone());
+                }
+            }
+        }
+    }
+
+    /// Settings that keyboard shortcuts should edit, the staged copy in manual apply mode.
+    fn edit_settings<'a>(&'a mut self, settings: &'a mut AppSettings) -> &'a mut AppSettings {
+        match self.staged_settings {
+            Some(ref mut staged) => staged,
+            None => {
+                self.compute_valid = false;
+                settings
+            }
         }
     }
 }
@@ -165,13 +822,43 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(settings: AppSettings) -> App {
+    /// Jumps to a location string as copied with the "Copy location" button.
+    pub fn goto(&mut self, location: &str) -> Result<(), String> {
+        let (zoom, settings) = ZoomState::parse_location(location, &self.settings)?;
+        self.state.zoomstate = zoom;
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// Opens the explorer window, which fails without an OpenGL 3.1 capable display.
+    pub fn new(settings: AppSettings) -> Result<App, String> {
         let event_loop = EventLoop::new();
-        let context = glutin::ContextBuilder::new().with_vsync(true);
-        let builder = glutin::window::WindowBuilder::new()
-            .with_title("mandelbrot explorer")
-            .with_inner_size(glutin::dpi::LogicalSize::new(1600f64, 900f64));
-        let display = glium::Display::new(builder, context, &event_loop).unwrap();
+        let context = glutin::ContextBuilder::new().with_vsync(settings.vsync);
+        let window_state = WindowState::load();
+        let mut builder = glutin::window::WindowBuilder::new().with_title("mandelbrot explorer");
+        builder = match &window_state {
+            Some(window) => builder.with_inner_size(glutin::dpi::PhysicalSize::new(
+                window.size[0],
+                window.size[1],
+            )),
+            None => builder.with_inner_size(glutin::dpi::LogicalSize::new(1600f64, 900f64)),
+        };
+        let display = glium::Display::new(builder, context, &event_loop).map_err(|e| {
+            format!(
+                "could not open an OpenGL window, the explorer needs a display with OpenGL 3.1 \
+                 or newer (use the render command for headless rendering): {}",
+                e
+            )
+        })?;
+        if let Some(window) = &window_state {
+            display
+                .gl_window()
+                .window()
+                .set_outer_position(glutin::dpi::PhysicalPosition::new(
+                    window.position[0],
+                    window.position[1],
+                ));
+        }
 
         let mut imgui = Context::create();
         imgui.set_ini_filename(None);
@@ -207,11 +894,11 @@ impl App {
             platform.attach_window(imgui.io_mut(), window, HiDpiMode::Rounded);
         }
 
-        let app_render = AppRenderer::init();
+        let app_render = AppRenderer::init(&display);
 
         let state = AppState::new(&settings);
 
-        App {
+        Ok(App {
             event_loop: event_loop,
             display: display,
             imgui: imgui,
@@ -219,43 +906,108 @@ impl App {
             app_render: app_render,
             state: state,
             settings: settings,
-        }
+        })
     }
 
-    fn recompute(
+    /// Computes the view on a new thread and hands the set to `deliver` unless cancelled,
+    /// returns the counters the workers update. Progress goes to `update_tx` if given.
+    fn recompute<D: FnOnce(ComputedSet) + Send + 'static>(
         zoomstate: &ZoomState,
         settings: &AppSettings,
-        tx: Sender<ComputedSet>,
-        update_tx: Sender<ComputeEvent>,
-    ) -> thread::JoinHandle<()> {
+        previous: Option<(ComputedSet, u64)>,
+        offset: [f64; 2],
+        cancel: Arc<AtomicBool>,
+        deliver: D,
+        update_tx: Option<Sender<ComputeEvent>>,
+    ) -> Arc<WorkerStats> {
+        let workers = Arc::new(WorkerStats::default());
         let prec = settings.precision;
+        let iteration_prec = settings.iteration_precision();
         let x = Float::with_val(prec, zoomstate.get_x());
         let y = Float::with_val(prec, zoomstate.get_y());
         let scale = Float::with_val(prec, zoomstate.get_scale());
+        let stretch = zoomstate.get_stretch();
         let [w, h] = settings.resolution;
         let engine = settings.engine;
+        let fractal = settings.fractal;
+        let invert = settings.invert;
+        let log_projection = settings.log_projection;
+        let angle = (settings.angle as f64).to_radians();
+        let bailout = settings.bailout as f64;
+        let stripe_density = settings.stripe_density as f64;
+        let distance = settings.distance();
+        let periods = settings.color.color_period;
+        let buddhabrot = settings.buddhabrot;
+        let buddhabrot_samples = settings.buddhabrot_samples as u64;
         let iterations = settings.iterations;
+        let center_out = settings.center_out;
+        let symmetry = settings.symmetry;
+        let single_threaded = settings.single_threaded;
+        let coordinates = settings.color.coordinates;
+        let stats = workers.clone();
         thread::spawn(move || {
-            tx.send(Compute::compute_set(
-                Some(&mut ThreadPool::new(8)),
-                Some(update_tx),
-                &ComputeSettings::new(
-                    x,
-                    y,
-                    scale,
-                    w,
-                    h,
-                    engine,
-                    BoundsSettings::new(iterations, prec),
+            let mut thread_pool = if single_threaded {
+                None
+            } else {
+                Some(ThreadPool::new(WORKER_THREADS))
+            };
+            let settings = ComputeSettings::new(
+                x,
+                y,
+                scale,
+                w,
+                h,
+                engine,
+                BoundsSettings::new(iterations, iteration_prec)
+                    .with_fractal(fractal)
+                    .with_bailout(bailout)
+                    .with_stripe_density(stripe_density)
+                    .with_distance(distance)
+                    .with_periods(periods),
+            )
+            .with_stretch(stretch)
+            .with_invert(invert)
+            .with_angle(angle)
+            .with_offset(offset)
+            .with_cancel(cancel.clone())
+            .with_workers(stats)
+            .with_center_out(center_out)
+            .with_symmetry(symmetry)
+            .with_progress_steps(PROGRESS_STEPS)
+            .with_precision(prec)
+            .with_coordinates(coordinates)
+            .with_log_projection(log_projection);
+            // forwards the progress to the UI until it stops listening
+            let forward = update_tx.map(|tx| move |event| tx.send(event).is_ok());
+            let progress = forward.as_ref().map(|f| f as &dyn Fn(ComputeEvent) -> bool);
+            let set = match previous {
+                _ if buddhabrot => Compute::compute_buddhabrot(
+                    thread_pool.as_mut(),
+                    progress,
+                    &settings,
+                    buddhabrot_samples,
+                    0,
                 ),
-            ))
-            .unwrap();
-        })
+                Some((previous, limit)) => {
+                    Compute::extend_set(thread_pool.as_mut(), progress, &settings, &previous, limit)
+                }
+                None => Compute::compute_set(thread_pool.as_mut(), progress, &settings),
+            };
+            match set {
+                Ok(set) if !cancel.load(Ordering::Relaxed) => deliver(set),
+                Ok(_) => {}
+                Err(e) => eprintln!("compute aborted: {}", e),
+            }
+        });
+        workers
     }
 
     pub fn run(self) {
         let (tx, rx) = channel();
+        let (sample_tx, sample_rx) = channel();
+        let (preview_tx, preview_rx) = channel();
         let (compute_tx, compute_rx) = channel();
+        let (compare_tx, compare_rx) = channel();
 
         self.display.gl_window().window().set_maximized(false);
 
@@ -278,6 +1030,17 @@ impl App {
             move |event: Event<()>, _target: &EventLoopWindowTarget<()>, flow: &mut ControlFlow| {
                 let gl_window = display.gl_window();
                 imgui_platform.handle_event(imgui.io_mut(), gl_window.window(), &event);
+                if let Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput { .. }
+                        | WindowEvent::MouseInput { .. }
+                        | WindowEvent::MouseWheel { .. }
+                        | WindowEvent::CursorMoved { .. },
+                    ..
+                } = event
+                {
+                    state.last_input = std::time::Instant::now();
+                }
 
                 match event {
                     Event::NewEvents(_) => {
@@ -305,6 +1068,11 @@ impl App {
                         event: WindowEvent::CloseRequested,
                         ..
                     } => {
+                        if let Some(window) = WindowState::of(gl_window.window()) {
+                            if let Err(e) = window.save() {
+                                eprintln!("failed to save {}: {}", WINDOW_STATE_FILE, e);
+                            }
+                        }
                         *flow = ControlFlow::Exit;
                     }
                     Event::WindowEvent {
@@ -313,10 +1081,15 @@ impl App {
                     } => {
                         if !imgui.io().want_capture_mouse {
                             let size = display.gl_window().window().inner_size();
-                            state.mouse_pos = [
-                                position.x / size.width as f64,
-                                position.y / size.height as f64,
-                            ];
+                            // relative to the set, which is letterboxed inside the window
+                            let matrix = view_matrix((size.width, size.height), &state, &settings);
+                            state.mouse_pos = view_position(
+                                [
+                                    position.x / size.width as f64,
+                                    position.y / size.height as f64,
+                                ],
+                                &matrix,
+                            );
                             state.mouse_end = state.mouse_pos;
                         }
                     }
@@ -341,21 +1114,50 @@ impl App {
                                     if !state.compute_busy && state.dragging {
                                         state.mouse_end = state.mouse_pos;
                                         state.dragging = false;
+                                        state.preview = None;
+                                        state.preview_zoom = None;
                                         let start = state.mouse_start;
                                         let end = state.mouse_end;
-                                        if (end[1] - start[1]) + (end[0] - start[0]) > 0.001 {
-                                            state.zoomstate.set_by_dragging(start, end, &settings);
-                                        } else {
-                                            state.zoomstate.zoom_position(start, {
-                                                if state.modifiers.shift() { 0.7 } else { 1.0 }
-                                            }, &settings);
-                                        }
+                                        let click_scale =
+                                            if state.modifiers.shift() { 0.7 } else { 1.0 };
+                                        state.zoomstate.finish_drag(
+                                            start,
+                                            end,
+                                            click_scale,
+                                            &settings,
+                                        );
                                         state.compute_valid = false;
                                     }
                                 }
                             }
                         }
                     }
+                    Event::WindowEvent {
+                        event:
+                            WindowEvent::MouseInput {
+                                state: ElementState::Pressed,
+                                button: MouseButton::Right,
+                                ..
+                            },
+                        ..
+                    } => {
+                        if !imgui.io().want_capture_mouse {
+                            let [x, y] = state.zoomstate.point_at(state.mouse_pos, &settings);
+                            let c = if settings.invert {
+                                let norm = x * x + y * y;
+                                [x / norm, -y / norm]
+                            } else {
+                                [x, y]
+                            };
+                            let bounds = BoundsSettings::new(
+                                settings.iterations,
+                                settings.iteration_precision(),
+                            )
+                            .with_fractal(settings.fractal)
+                            .with_bailout(settings.bailout as f64);
+                            state.orbit = orbit_path(c, &bounds, ORBIT_POINTS);
+                        }
+                    }
                     Event::WindowEvent {
                         event:
                             WindowEvent::MouseWheel {
@@ -381,23 +1183,321 @@ impl App {
                     } => {
                         state.modifiers = modifiers;
                     }
+                    Event::WindowEvent {
+                        event:
+                            WindowEvent::KeyboardInput {
+                                input:
+                                    KeyboardInput {
+                                        state: ElementState::Pressed,
+                                        virtual_keycode: Some(VirtualKeyCode::E),
+                                        ..
+                                    },
+                                ..
+                            },
+                        ..
+                    } => {
+                        if !imgui.io().want_capture_keyboard {
+                            let settings = state.edit_settings(settings);
+                            let index = settings.engine.to_usize().unwrap();
+                            settings.engine =
+                                ComputeEngine::LIST[(index + 1) % ComputeEngine::LIST.len()];
+                        }
+                    }
+                    // debug view of the point computed for every pixel, left out of the help
+                    Event::WindowEvent {
+                        event:
+                            WindowEvent::KeyboardInput {
+                                input:
+                                    KeyboardInput {
+                                        state: ElementState::Pressed,
+                                        virtual_keycode: Some(VirtualKeyCode::F12),
+                                        ..
+                                    },
+                                ..
+                            },
+                        ..
+                    } => {
+                        if !imgui.io().want_capture_keyboard {
+                            let settings = state.edit_settings(settings);
+                            settings.color.coordinates = !settings.color.coordinates;
+                        }
+                    }
+                    Event::WindowEvent {
+                        event:
+                            WindowEvent::KeyboardInput {
+                                input:
+                                    KeyboardInput {
+                                        state: ElementState::Pressed,
+                                        virtual_keycode: Some(key),
+                                        ..
+                                    },
+                                ..
+                            },
+                        ..
+                    } if matches!(
+                        key,
+                        VirtualKeyCode::Equals
+                            | VirtualKeyCode::Add
+                            | VirtualKeyCode::Minus
+                            | VirtualKeyCode::Subtract
+                    ) =>
+                    {
+                        if !imgui.io().want_capture_keyboard {
+                            let step = if state.modifiers.shift() {
+                                ITERATION_STEP * 10
+                            } else {
+                                ITERATION_STEP
+                            };
+                            let settings = state.edit_settings(settings);
+                            settings.iterations = match key {
+                                VirtualKeyCode::Equals | VirtualKeyCode::Add => {
+                                    settings.iterations + step
+                                }
+                                _ => settings.iterations.saturating_sub(step).max(1),
+                            };
+                        }
+                    }
+                    Event::WindowEvent {
+                        event:
+                            WindowEvent::KeyboardInput {
+                                input:
+                                    KeyboardInput {
+                                        state: ElementState::Pressed,
+                                        virtual_keycode: Some(key),
+                                        ..
+                                    },
+                                ..
+                            },
+                        ..
+                    } if matches!(
+                        key,
+                        VirtualKeyCode::Left
+                            | VirtualKeyCode::Right
+                            | VirtualKeyCode::Up
+                            | VirtualKeyCode::Down
+                    ) =>
+                    {
+                        if !imgui.io().want_capture_keyboard && !state.compute_busy {
+                            let direction = match key {
+                                VirtualKeyCode::Left => [-1.0, 0.0],
+                                VirtualKeyCode::Right => [1.0, 0.0],
+                                VirtualKeyCode::Up => [0.0, 1.0],
+                                _ => [0.0, -1.0],
+                            };
+                            if state.modifiers.shift() {
+                                state.zoomstate.pan_pixels(direction, &settings);
+                            } else {
+                                state.zoomstate.shift(
+                                    [direction[0] * PAN_STEP, direction[1] * PAN_STEP],
+                                    &settings,
+                                );
+                            }
+                            state.compute_valid = false;
+                        }
+                    }
                     _ => {}
                 }
 
-                if !state.compute_valid {
-                    App::recompute(&state.zoomstate, &settings, tx.clone(), compute_tx.clone());
+                if let Some(since) = state.draft_since {
+                    if since.elapsed() > DRAFT_IDLE
+                        && state.compute_valid
+                        && !state.compute_busy
+                        && !state.dragging
+                    {
+                        state.refining = true;
+                        state.compute_valid = false;
+                    }
+                }
+
+                if !state.compute_valid && state.compute_enabled {
+                    // when only the iterations went up the bounded pixels can be continued
+                    let previous = match (&state.view_zoom, &state.view_settings) {
+                        (Some(zoom), Some(view))
+                            if *zoom == state.zoomstate
+                                && view.same_view(&settings)
+                                && view.iterations < settings.iterations =>
+                        {
+                            Some((state.computed_set.clone(), view.iterations))
+                        }
+                        _ => None,
+                    };
+                    let mut compute_settings = settings.clone();
+                    if settings.compute_budget > 0 && state.draft_divisor > 1 && !state.refining {
+                        // keep roughly the same aspect ratio
+                        let [w, h] = settings.resolution;
+                        let width = (w / state.draft_divisor).max(1);
+                        compute_settings.resolution = [width, (h * width / w).max(1)];
+                    }
+                    // a render still running is superseded by this one
+                    state.compute_cancel.store(true, Ordering::Relaxed);
+                    state.compute_cancel = Arc::new(AtomicBool::new(false));
+                    state.compute_generation += 1;
+                    state.sample_cancel.store(true, Ordering::Relaxed);
+                    state.sample_busy = false;
+                    let generation = state.compute_generation;
+                    let tx = tx.clone();
+                    state.workers = App::recompute(
+                        &state.zoomstate,
+                        &compute_settings,
+                        previous,
+                        [0.0, 0.0],
+                        state.compute_cancel.clone(),
+                        // the window may already be closed, then there is nobody to receive it
+                        move |set| {
+                            tx.send((generation, set)).ok();
+                        },
+                        Some(compute_tx.clone()),
+                    );
+                    if settings.compare {
+                        let mut compare_settings = compute_settings.clone();
+                        compare_settings.engine = settings.compare_engine;
+                        let compare_tx = compare_tx.clone();
+                        App::recompute(
+                            &state.zoomstate,
+                            &compare_settings,
+                            None,
+                            [0.0, 0.0],
+                            state.compute_cancel.clone(),
+                            move |set| {
+                                compare_tx.send((generation, set)).ok();
+                            },
+                            // the progress bar follows the main render only
+                            None,
+                        );
+                    }
                     state.compute_valid = true;
                     state.compute_busy = true;
+                    state.compute_zoom = Some(state.zoomstate.clone());
+                    state.compute_settings = Some(compute_settings);
                     state.compute_start = Some(std::time::Instant::now());
                     state.compute_time = None;
                 }
 
-                if let Ok(result) = rx.try_recv() {
+                if settings.drag_preview
+                    && state.dragging
+                    && !state.preview_busy
+                    && state.compute_enabled
+                {
+                    let (start, end) = (state.mouse_start, state.mouse_end);
+                    let mut target = state.zoomstate.clone();
+                    target.set_by_dragging(start, end, &settings);
+                    if (end[0] - start[0]).abs() > 0.01
+                        && (end[1] - start[1]).abs() > 0.01
+                        && state.preview_zoom.as_ref() != Some(&target)
+                    {
+                        let mut preview_settings = settings.clone();
+                        preview_settings.resolution = PREVIEW_SIZE;
+                        App::recompute(
+                            &target,
+                            &preview_settings,
+                            None,
+                            [0.0, 0.0],
+                            Arc::new(AtomicBool::new(false)),
+                            {
+                                let preview_tx = preview_tx.clone();
+                                move |set| {
+                                    preview_tx.send(set).ok();
+                                }
+                            },
+                            None,
+                        );
+                        state.preview_zoom = Some(target);
+                        state.preview_busy = true;
+                    }
+                }
+
+                if let Ok(preview) = preview_rx.try_recv() {
+                    // a preview finishing after the mouse was released is of no use anymore
+                    if state.dragging {
+                        state.preview = Some(preview);
+                        state.preview_valid = false;
+                    }
+                    state.preview_busy = false;
+                }
+
+                // refine an unchanged view with jittered subsamples while nothing else runs, on
+                // request or once the user has left the view alone for a while
+                let idle = settings.idle_quality && state.last_input.elapsed() > IDLE_QUALITY_DELAY;
+                let view_current = match (&state.view_zoom, &state.view_settings) {
+                    (Some(zoom), Some(view)) => {
+                        *zoom == state.zoomstate && view.same_view(&settings)
+                    }
+                    _ => false,
+                };
+                if (settings.accumulate || idle)
+                    && view_current
+                    && state.compute_enabled
+                    && state.compute_valid
+                    && !state.compute_busy
+                    && !state.sample_busy
+                    && state.samples < MAX_SAMPLES
+                {
+                    let offset = subpixel_offset(settings.jitter_seed, state.samples);
+                    state.sample_cancel = Arc::new(AtomicBool::new(false));
+                    App::recompute(
+                        &state.zoomstate,
+                        state.view_settings.as_ref().unwrap(),
+                        None,
+                        offset,
+                        state.sample_cancel.clone(),
+                        {
+                            let sample_tx = sample_tx.clone();
+                            move |set| {
+                                sample_tx.send(set).ok();
+                            }
+                        },
+                        None,
+                    );
+                    state.sample_busy = true;
+                    state.samples += 1;
+                }
+
+                if let Ok(sample) = sample_rx.try_recv() {
+                    state.pending_samples.push(sample);
+                    state.sample_busy = false;
+                }
+
+                let compare = compare_rx
+                    .try_iter()
+                    .filter(|(generation, _)| *generation == state.compute_generation)
+                    .last();
+                if let Some((_, compare)) = compare {
+                    state.compare_set = Some(compare);
+                    state.compare_valid = false;
+                }
+
+                // sets of renders that were superseded while running are dropped
+                let result = rx
+                    .try_iter()
+                    .filter(|(generation, _)| *generation == state.compute_generation)
+                    .last();
+                if let Some((_, result)) = result {
                     state.computed_set = result;
                     state.set_valid = false;
+                    state.pending_samples.clear();
+                    state.samples = 1;
+                    state.view_zoom = state.compute_zoom.take();
+                    state.view_settings = state.compute_settings.take();
                     state.compute_busy = false;
-                    state.compute_time = Some(state.compute_start.unwrap().elapsed());
+                    let compute_time = state.compute_start.unwrap().elapsed();
+                    state.compute_time = Some(compute_time);
                     state.compute_start = None;
+                    let budget = std::time::Duration::from_millis(settings.compute_budget as u64);
+                    if settings.compute_budget > 0 && !state.refining {
+                        if compute_time > budget {
+                            state.draft_divisor = (state.draft_divisor * 2).min(MAX_DRAFT_DIVISOR);
+                        } else if compute_time < budget / 4 {
+                            state.draft_divisor = (state.draft_divisor / 2).max(1);
+                        }
+                    }
+                    let draft =
+                        state.view_settings.as_ref().unwrap().resolution != settings.resolution;
+                    state.draft_since = if draft {
+                        Some(std::time::Instant::now())
+                    } else {
+                        None
+                    };
+                    state.refining = false;
                 }
 
                 for event in compute_rx.try_iter() {
@@ -422,10 +1522,15 @@ impl App {
 
         let mut target = display.draw();
         target.clear_color_srgb(1.0, 1.0, 1.0, 1.0);
-        app_render.render(state, &mut target, display);
+        if !state.set_valid {
+            state.histogram = state.computed_set.histogram(HISTOGRAM_BINS);
+        }
+        app_render.render(state, settings, &mut target, display);
         //platform.borrow().prepare_render(&ui, &window);
         let ui = imgui.frame();
-        Self::build_ui(&ui, state, settings);
+        state.build_staged(settings, |state, settings| {
+            Self::build_ui(&ui, state, settings)
+        });
         //render ui
         let draw_data = ui.render();
         // render mandelbrot
@@ -436,6 +1541,48 @@ impl App {
         target.finish().unwrap();
     }
 
+    /// Strip of the palette over the escape counts of the shown set, with labelled ticks.
+    fn build_legend(ui: &imgui::Ui, state: &AppState, settings: &AppSettings) {
+        const STRIP_HEIGHT: f32 = 12.0;
+        const TICK_HEIGHT: f32 = 4.0;
+        let limit = state.computed_set.get_limit();
+        let colors = settings
+            .color
+            .legend(limit, state.computed_set.get_bailout(), LEGEND_STEPS);
+        let [x, y] = ui.cursor_screen_pos();
+        let width = ui.content_region_avail()[0];
+        let segment = width / LEGEND_STEPS as f32;
+        let draw_list = ui.get_window_draw_list();
+        for (i, color) in colors.into_iter().enumerate() {
+            let left = x + i as f32 * segment;
+            // overlap the next segment by a pixel so no gaps show between them
+            draw_list
+                .add_rect([left, y], [left + segment + 1.0, y + STRIP_HEIGHT], color)
+                .filled(true)
+                .build();
+        }
+        let mut height = STRIP_HEIGHT;
+        for tick in 0..=LEGEND_TICKS {
+            let tick_x = x + width * tick as f32 / LEGEND_TICKS as f32;
+            let bottom = y + STRIP_HEIGHT + TICK_HEIGHT;
+            draw_list
+                .add_line(
+                    [tick_x, y + STRIP_HEIGHT],
+                    [tick_x, bottom],
+                    [1.0, 1.0, 1.0, 1.0],
+                )
+                .build();
+            let label = im_str!("{}", limit * tick / LEGEND_TICKS);
+            let size = ui.calc_text_size(&label, false, -1.0);
+            // center the label under its tick without leaving the strip
+            let label_x = (tick_x - size[0] / 2.0).max(x).min(x + width - size[0]);
+            draw_list.add_text([label_x, bottom], [1.0, 1.0, 1.0, 1.0], label.to_str());
+            height = height.max(STRIP_HEIGHT + TICK_HEIGHT + size[1]);
+        }
+        drop(draw_list);
+        ui.dummy([width, height]);
+    }
+
     fn build_ui(ui: &imgui::Ui, state: &mut AppState, settings: &mut AppSettings) {
         imgui::Window::new(im_str!("Mandelbrot-explorer"))
             .size([400.0, 600.0], Condition::FirstUseEver)
@@ -447,18 +1594,205 @@ impl App {
                 ));
                 ui.separator();
                 ui.text(im_str!("Scale:\n\t{:.4}", state.zoomstate.get_scale()));
+                ui.text(im_str!(
+                    "Aspect stretch:\n\t{:.4}",
+                    state.zoomstate.get_stretch()
+                ));
+                // the shown set may lag behind the zoom state while it is being computed
+                let [x, y] = state
+                    .view_zoom
+                    .as_ref()
+                    .unwrap_or(&state.zoomstate)
+                    .point_at(state.mouse_pos, &settings);
+                let (width, height) = state.computed_set.get_size();
+                let pixel = (
+                    (state.mouse_pos[0] * width as f64).floor(),
+                    ((1.0 - state.mouse_pos[1]) * height as f64).floor(),
+                );
+                let iterations = if pixel.0 >= 0.0 && pixel.1 >= 0.0 {
+                    state.computed_set.get(pixel.0 as u32, pixel.1 as u32)
+                } else {
+                    None
+                };
+                ui.text(im_str!(
+                    "Under cursor:\n\tX:{:.6}\n\tY:{:.6}\n\tIterations: {}",
+                    x,
+                    y,
+                    match iterations {
+                        Some(Bound::Unbounded(n, _)) => n.to_string(),
+                        Some(Bound::Bounded(_)) => String::from("bounded"),
+                        None => String::from("n/a"),
+                    }
+                ));
+                ui.checkbox(im_str!("Free aspect selection"), &mut settings.free_aspect);
+                ui.checkbox(im_str!("Show crosshair"), &mut settings.show_crosshair);
+                ui.checkbox(im_str!("Show axes"), &mut settings.show_axes);
+                ui.checkbox(im_str!("Show minimap"), &mut settings.show_minimap);
+                ui.checkbox(im_str!("Smooth sampling"), &mut settings.smooth_sampling);
+                ui.checkbox(im_str!("Show workers"), &mut settings.show_workers);
+                ui.checkbox(im_str!("Lock aspect ratio"), &mut settings.lock_aspect);
+                ui.checkbox(
+                    im_str!("Progressive anti-aliasing"),
+                    &mut settings.accumulate,
+                );
+                ui.checkbox(im_str!("Anti-alias when idle"), &mut settings.idle_quality);
+                let mut jitter_seed = settings.jitter_seed as i32;
+                ui.input_int(im_str!("Jitter seed"), &mut jitter_seed)
+                    .build();
+                settings.jitter_seed = jitter_seed.max(0) as u64;
+                ui.checkbox(im_str!("Preview selection"), &mut settings.drag_preview);
+                ui.checkbox(im_str!("Center-out rows"), &mut settings.center_out);
+                ui.checkbox(im_str!("Mirror real axis"), &mut settings.symmetry);
+                ui.checkbox(im_str!("Single-threaded"), &mut settings.single_threaded);
+                imgui::ColorEdit::new(im_str!("Selection color"), &mut settings.selection_color)
+                    .build(&ui);
+                imgui::Slider::new(im_str!("Dim outside selection"), 0.0..=1.0)
+                    .build(&ui, &mut settings.selection_dim);
                 ui.separator();
-                if ui.button(im_str!("Render"), [60.0, 20.0]) && !state.compute_busy {
+                if ui.button(im_str!("Render"), [60.0, 20.0]) {
+                    state.apply_staged = true;
                     state.compute_valid = false;
                 };
                 if ui.button(im_str!("Reset"), [60.0, 20.0]) && !state.compute_busy {
-                    state.zoomstate = ZoomState::new(&settings);
-                    state.compute_valid = false;
+                    state.move_view(ZoomState::new(&settings));
+                }
+                if state.compute_busy && ui.button(im_str!("Cancel"), [60.0, 20.0]) {
+                    state.compute_cancel.store(true, Ordering::Relaxed);
+                    state.compute_generation += 1;
+                    state.compute_busy = false;
+                    state.compute_zoom = None;
+                    state.compute_settings = None;
+                    state.compute_start = None;
+                    state.progress = ComputeEvent::End;
+                    state.refining = false;
+                }
+                let mut paused = !state.compute_enabled;
+                if ui.checkbox(im_str!("Pause compute"), &mut paused) {
+                    // whatever was requested meanwhile is computed once when resuming
+                    state.compute_enabled = !paused;
+                }
+                ui.checkbox(im_str!("Manual apply"), &mut state.manual_apply);
+                let labels = [im_str!("Center x"), im_str!("Center y"), im_str!("Height")];
+                for (i, label) in labels.iter().enumerate() {
+                    let highlight = if state.coordinate_invalid[i] {
+                        Some(ui.push_style_color(StyleColor::FrameBg, [0.6, 0.1, 0.1, 1.0]))
+                    } else {
+                        None
+                    };
+                    ui.input_text(label, &mut state.coordinate_input[i]).build();
+                    if let Some(highlight) = highlight {
+                        highlight.pop(&ui);
+                    }
+                }
+                if ui.button(im_str!("Go to"), [0.0, 20.0]) {
+                    let [x, y, scale] = &state.coordinate_input;
+                    state.coordinate_invalid = state
+                        .zoomstate
+                        .set_coordinates([x.to_str(), y.to_str(), scale.to_str()], &settings);
+                    if !state.coordinate_invalid.contains(&true) {
+                        state.compute_valid = false;
+                        state.view_replaced = true;
+                    }
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Current view"), [0.0, 20.0]) {
+                    let zoom = &state.zoomstate;
+                    let values = [zoom.get_x(), zoom.get_y(), zoom.get_scale()];
+                    for (input, value) in state.coordinate_input.iter_mut().zip(values.iter()) {
+                        input.clear();
+                        input.push_str(&value.to_string());
+                    }
+                    state.coordinate_invalid = [false; 3];
+                }
+                if ui.button(im_str!("Copy location"), [0.0, 20.0]) {
+                    let location = state.zoomstate.location(&settings);
+                    println!("{}", location);
+                    if let Err(e) = arboard::Clipboard::new()
+                        .and_then(|mut clipboard| clipboard.set_text(location))
+                    {
+                        eprintln!("failed to copy location: {}", e);
+                    }
+                }
+                if ui.button(im_str!("Copy render command"), [0.0, 20.0]) {
+                    let command = state.zoomstate.render_command(&settings);
+                    println!("{}", command);
+                    if let Err(e) = arboard::Clipboard::new()
+                        .and_then(|mut clipboard| clipboard.set_text(command))
+                    {
+                        eprintln!("failed to copy render command: {}", e);
+                    }
+                }
+                if ui.button(im_str!("Copy image"), [0.0, 20.0]) {
+                    if let Err(e) = copy_image(&state.computed_set, &settings.color) {
+                        eprintln!("failed to copy image: {}", e);
+                    }
+                }
+                if ui.button(im_str!("Queue job"), [0.0, 20.0]) {
+                    let location = state.zoomstate.location(&settings);
+                    if let Err(e) = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(JOBS_FILE)
+                        .and_then(|mut file| writeln!(file, "{}", location))
+                    {
+                        eprintln!("failed to queue job in {}: {}", JOBS_FILE, e);
+                    }
+                }
+                if ui.button(im_str!("Save set"), [60.0, 20.0]) {
+                    if let Err(e) = std::fs::File::create(COMPUTED_SET_FILE).and_then(|file| {
+                        let mut writer = std::io::BufWriter::new(file);
+                        state.computed_set.save(&mut writer)?;
+                        writer.flush()
+                    }) {
+                        eprintln!("failed to save {}: {}", COMPUTED_SET_FILE, e);
+                    }
+                }
+                if ui.button(im_str!("Load set"), [60.0, 20.0]) && !state.compute_busy {
+                    match std::fs::File::open(COMPUTED_SET_FILE)
+                        .and_then(|file| ComputedSet::load(&mut std::io::BufReader::new(file)))
+                    {
+                        Ok(set) => state.show_loaded_set(set),
+                        Err(e) => eprintln!("failed to load {}: {}", COMPUTED_SET_FILE, e),
+                    }
+                }
+                if !state.orbit.is_empty() && ui.button(im_str!("Clear orbit"), [0.0, 20.0]) {
+                    state.orbit.clear();
+                }
+                ui.separator();
+                for (label, engine, precision, iterations) in PRESETS.iter() {
+                    if ui.button(&im_str!("{}", label), [0.0, 20.0]) && !state.compute_busy {
+                        settings.engine = *engine;
+                        settings.precision = *precision;
+                        settings.iteration_precision = 0;
+                        settings.iterations = *iterations;
+                        state.compute_valid = false;
+                    }
+                }
+                ui.separator();
+                for (label, x, y, scale, iterations) in LOCATIONS.iter() {
+                    if ui.button(&im_str!("{}", label), [0.0, 20.0]) && !state.compute_busy {
+                        settings.iterations = *iterations;
+                        state.move_view(ZoomState::at(x, y, scale, &settings));
+                    }
                 }
                 ui.separator();
                 let mut iterations = settings.iterations as i32;
                 ui.input_int(im_str!("Iterations"), &mut iterations).build();
                 settings.iterations = iterations as u64;
+                ui.input_float(im_str!("Bailout radius"), &mut settings.bailout)
+                    .build();
+                settings.bailout = settings.bailout.max(2.0);
+                ui.input_float(
+                    im_str!("Stripe density (0: off)"),
+                    &mut settings.stripe_density,
+                )
+                .build();
+                settings.stripe_density = settings.stripe_density.max(0.0);
+                ui.checkbox(im_str!("Buddhabrot"), &mut settings.buddhabrot);
+                let mut buddhabrot_samples = settings.buddhabrot_samples as i32;
+                ui.input_int(im_str!("Buddhabrot samples"), &mut buddhabrot_samples)
+                    .build();
+                settings.buddhabrot_samples = buddhabrot_samples.max(1) as u32;
                 ui.separator();
                 let items: Vec<_> = ComputeEngine::LIST
                     .iter()
@@ -473,11 +1807,275 @@ impl App {
                 ) {
                     settings.engine = FromPrimitive::from_i32(select).unwrap()
                 }
+                if state.zoomstate.exceeds_precision(&settings) {
+                    // points are placed at the precision bits, iterating them with more won't help
+                    let engine_bits = settings
+                        .engine
+                        .mantissa_bits(settings.iteration_precision());
+                    let hint = match settings.engine {
+                        ComputeEngine::Precision => "raise the precision bits",
+                        _ if engine_bits > settings.precision => "raise the precision bits",
+                        _ if engine_bits == settings.precision => {
+                            "switch to the Precision engine and raise the precision bits"
+                        }
+                        _ => "switch to the Precision engine",
+                    };
+                    ui.text_colored(
+                        [1.0, 0.6, 0.2, 1.0],
+                        im_str!("{:?} precision exceeded, {}", settings.engine, hint),
+                    );
+                }
+                if ui.checkbox(im_str!("Compare engines"), &mut settings.compare) {
+                    state.compare_set = None;
+                    state.compute_valid = false;
+                }
+                if settings.compare {
+                    let mut select: i32 = settings.compare_engine.to_i32().unwrap();
+                    if ui.list_box(
+                        im_str!("Right engine"),
+                        &mut select,
+                        items.iter().collect::<Vec<_>>().as_slice(),
+                        items.len() as i32,
+                    ) {
+                        settings.compare_engine = FromPrimitive::from_i32(select).unwrap();
+                        state.compare_set = None;
+                        state.compute_valid = false;
+                    }
+                }
+                ui.separator();
+                let items: Vec<_> = Fractal::LIST.iter().map(|x| im_str!("{:?}", x)).collect();
+                let mut select: i32 = settings.fractal.to_i32().unwrap();
+                if ui.list_box(
+                    im_str!("Fractal"),
+                    &mut select,
+                    items.iter().collect::<Vec<_>>().as_slice(),
+                    items.len() as i32,
+                ) {
+                    settings.fractal = FromPrimitive::from_i32(select).unwrap()
+                }
+                ui.checkbox(im_str!("Invert (1/c)"), &mut settings.invert);
+                ui.checkbox(im_str!("Log projection"), &mut settings.log_projection);
+                imgui::Slider::new(im_str!("Rotation"), -180.0..=180.0)
+                    .build(&ui, &mut settings.angle);
+                ui.separator();
+                ui.input_int(im_str!("Width"), &mut state.resolution_input[0])
+                    .build();
+                ui.input_int(im_str!("Height"), &mut state.resolution_input[1])
+                    .build();
+                if ui.button(im_str!("Apply size"), [0.0, 20.0]) {
+                    let [w, h] = state.resolution_input;
+                    let resolution = [w.max(1) as u32, h.max(1) as u32];
+                    state.resolution_input = [resolution[0] as i32, resolution[1] as i32];
+                    if resolution != settings.resolution {
+                        settings.resolution = resolution;
+                        // a render still running is cancelled once the new one starts
+                        state.compute_valid = false;
+                    }
+                }
+                let mut compute_budget = settings.compute_budget as i32;
+                ui.input_int(im_str!("Compute budget (ms, 0: off)"), &mut compute_budget)
+                    .build();
+                settings.compute_budget = compute_budget.max(0) as u32;
                 ui.separator();
                 let mut precision = settings.precision as i32;
                 ui.input_int(im_str!("Precision bits"), &mut precision)
                     .build();
-                settings.precision = precision as u32;
+                settings.precision = precision.max(1).min(MAX_PRECISION as i32) as u32;
+                let mut iteration_precision = settings.iteration_precision as i32;
+                ui.input_int(
+                    im_str!("Iteration bits (0: same)"),
+                    &mut iteration_precision,
+                )
+                .build();
+                settings.iteration_precision =
+                    iteration_precision.max(0).min(MAX_PRECISION as i32) as u32;
+                if settings.iteration_precision() > PRECISION_WARNING {
+                    // multiplying n limb floats takes about n^1.6 limb products
+                    let limbs = (settings.iteration_precision() as f64 / 64.0).ceil();
+                    ui.text_colored(
+                        [1.0, 0.6, 0.2, 1.0],
+                        im_str!(
+                            "Very high precision, pixels take ~{:.0}x as long as at 64 bits",
+                            limbs.powf(1.6)
+                        ),
+                    );
+                }
+                ui.separator();
+                let items: Vec<_> = ColorMode::LIST.iter().map(|x| im_str!("{:?}", x)).collect();
+                let mut select: i32 = settings.color.mode.to_i32().unwrap();
+                if ui.list_box(
+                    im_str!("Coloring"),
+                    &mut select,
+                    items.iter().collect::<Vec<_>>().as_slice(),
+                    items.len() as i32,
+                ) {
+                    let distance = settings.distance();
+                    settings.color.mode = FromPrimitive::from_i32(select).unwrap();
+                    state.set_valid = false;
+                    if settings.distance() != distance {
+                        state.compute_valid = false;
+                    }
+                }
+                // only the scalar engines track the stripe average and the derivative, the
+                // latter for the Mandelbrot set only
+                let scalar = matches!(
+                    settings.engine,
+                    ComputeEngine::Single | ComputeEngine::Double
+                );
+                let warning = match settings.color.mode {
+                    ColorMode::Distance if !scalar || settings.fractal != Fractal::Mandelbrot => {
+                        Some("Distance needs the Mandelbrot set on the Single or Double engine")
+                    }
+                    ColorMode::Stripe if !scalar => {
+                        Some("Stripe needs the Single or Double engine")
+                    }
+                    ColorMode::Stripe if settings.stripe_density <= 0.0 => {
+                        Some("Stripe needs a stripe density above 0")
+                    }
+                    _ => None,
+                };
+                if let Some(warning) = warning {
+                    ui.text_colored([1.0, 0.6, 0.2, 1.0], im_str!("{}", warning));
+                }
+                let items: Vec<_> = TransferCurve::LIST
+                    .iter()
+                    .map(|x| im_str!("{:?}", x))
+                    .collect();
+                let mut select: i32 = settings.color.transfer.to_i32().unwrap();
+                if ui.list_box(
+                    im_str!("Transfer curve"),
+                    &mut select,
+                    items.iter().collect::<Vec<_>>().as_slice(),
+                    items.len() as i32,
+                ) {
+                    settings.color.transfer = FromPrimitive::from_i32(select).unwrap();
+                    state.set_valid = false;
+                }
+                if imgui::Slider::new(im_str!("Power exponent"), 0.1..=8.0)
+                    .build(&ui, &mut settings.color.transfer_exponent)
+                {
+                    state.set_valid = false;
+                }
+                if imgui::Slider::new(im_str!("Palette offset"), 0.0..=1.0)
+                    .build(&ui, &mut settings.color.palette_offset)
+                {
+                    state.set_valid = false;
+                }
+                if imgui::Slider::new(im_str!("Color density"), 0.1..=16.0)
+                    .build(&ui, &mut settings.color.density)
+                {
+                    state.set_valid = false;
+                }
+                if imgui::Slider::new(im_str!("Gamma"), 0.1..=4.0)
+                    .build(&ui, &mut settings.color.gamma)
+                {
+                    state.set_valid = false;
+                }
+                let mut color_steps = settings.color.color_steps as i32;
+                if ui
+                    .input_int(im_str!("Color steps (0: smooth)"), &mut color_steps)
+                    .build()
+                {
+                    settings.color.color_steps = color_steps.max(0) as u32;
+                    state.set_valid = false;
+                }
+                if ui.checkbox(
+                    im_str!("Color interior by period"),
+                    &mut settings.color.color_period,
+                ) {
+                    // the periods are only looked for while they are colored
+                    state.compute_valid = false;
+                    state.set_valid = false;
+                }
+                let mut palette_file = None;
+                if ui.button(im_str!("Load palette"), [0.0, 20.0]) {
+                    palette_file = Some(PALETTE_FILE);
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Image palette"), [0.0, 20.0]) {
+                    palette_file = Some(PALETTE_IMAGE_FILE);
+                }
+                if let Some(file) = palette_file {
+                    settings.color.gradient = match load_gradient(std::path::Path::new(file)) {
+                        Ok(gradient) => Some(gradient),
+                        Err(e) => {
+                            eprintln!("failed to load palette, using the default: {}", e);
+                            None
+                        }
+                    };
+                    state.set_valid = false;
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Default palette"), [0.0, 20.0]) {
+                    settings.color.gradient = None;
+                    state.set_valid = false;
+                }
+                // the hue wheel is not blended, only a loaded palette is
+                if settings.color.gradient.is_some()
+                    && ui.checkbox(im_str!("Blend palette in Lch"), &mut settings.color.lch)
+                {
+                    state.set_valid = false;
+                }
+                if settings.color.gradient.is_none() {
+                    if imgui::Slider::new(im_str!("Hue saturation"), 0.0..=1.0)
+                        .build(&ui, &mut settings.color.saturation)
+                    {
+                        state.set_valid = false;
+                    }
+                    if imgui::Slider::new(im_str!("Hue value"), 0.0..=1.0)
+                        .build(&ui, &mut settings.color.value)
+                    {
+                        state.set_valid = false;
+                    }
+                }
+                if ui.checkbox(im_str!("Iteration bands"), &mut settings.color.bands) {
+                    state.set_valid = false;
+                }
+                let mut band_interval = settings.color.band_interval as i32;
+                if ui
+                    .input_int(im_str!("Band interval"), &mut band_interval)
+                    .build()
+                {
+                    settings.color.band_interval = band_interval.max(1) as u32;
+                    state.set_valid = false;
+                }
+                if imgui::ColorEdit::new(im_str!("Band color"), &mut settings.color.band_color)
+                    .build(&ui)
+                {
+                    state.set_valid = false;
+                }
+                if let ColorMode::Edges = settings.color.mode {
+                    if ui
+                        .input_float(
+                            im_str!("Edge threshold"),
+                            &mut settings.color.edge_threshold,
+                        )
+                        .build()
+                    {
+                        state.set_valid = false;
+                    }
+                    if imgui::ColorEdit::new(im_str!("Edge color"), &mut settings.color.edge_color)
+                        .build(&ui)
+                    {
+                        state.set_valid = false;
+                    }
+                    if imgui::ColorEdit::new(
+                        im_str!("Edge background"),
+                        &mut settings.color.edge_background,
+                    )
+                    .build(&ui)
+                    {
+                        state.set_valid = false;
+                    }
+                }
+                if imgui::ColorEdit::new(
+                    im_str!("Interior color"),
+                    &mut settings.color.interior_color,
+                )
+                .build(&ui)
+                {
+                    state.set_valid = false;
+                }
                 ui.separator();
                 imgui::ProgressBar::new(match state.progress {
                     ComputeEvent::Progress((a, b)) => a as f32 / b as f32,
@@ -485,13 +2083,42 @@ impl App {
                 })
                 .build(&ui);
 
+                ui.plot_histogram(im_str!("Escape iterations"), &state.histogram)
+                    .graph_size([0.0, 60.0])
+                    .build();
+                Self::build_legend(&ui, state, settings);
                 ui.separator();
+                if settings.compare {
+                    ui.text(im_str!(
+                        "Engines: {:?} | {:?}",
+                        settings.engine,
+                        settings.compare_engine
+                    ));
+                } else {
+                    ui.text(im_str!("Engine: {:?}", settings.engine));
+                }
+                if let (true, Some(view)) = (state.compute_busy, &state.view_settings) {
+                    ui.text(im_str!(
+                        "Showing previous set ({} bits, {} iterations)",
+                        view.precision,
+                        view.iterations
+                    ));
+                }
                 ui.text(im_str!("Render time:"));
                 if let Some(duration) = state.compute_time {
                     ui.text(im_str!("\t{:.4} seconds", duration.as_secs_f64()));
                 } else {
                     ui.text(im_str!("\tn/a"));
                 }
+                if let (true, Some(start)) = (settings.show_workers, state.compute_start) {
+                    let rows = state.workers.rows() as f64 / start.elapsed().as_secs_f64();
+                    ui.text(im_str!(
+                        "Workers busy: {}/{}",
+                        state.workers.busy(),
+                        WORKER_THREADS
+                    ));
+                    ui.text(im_str!("Rows per second: {:.0}", rows));
+                }
                 ui.separator();
                 ui.text(im_str!(r"
 Area drag: zoom in on area
@@ -500,7 +2127,149 @@ shift+click: click zoom in on position
 ctrl+scroll: zoom in on center
 scroll: zoom in and move to position
 hold shift: zoom more
+right click: show the orbit of a point
+E: switch to the next engine
++/-: change the iterations, more with shift
+arrows: pan, by a single pixel with shift
                 "))
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drags_zoom_in_on_the_box_in_every_direction() {
+        let settings = AppSettings::new();
+        let mut expected = ZoomState::new(&settings);
+        expected.set_by_dragging([0.4, 0.3], [0.6, 0.5], &settings);
+        let drags = [
+            ([0.4, 0.3], [0.6, 0.5]),
+            ([0.6, 0.5], [0.4, 0.3]),
+            ([0.4, 0.5], [0.6, 0.3]),
+            ([0.6, 0.3], [0.4, 0.5]),
+        ];
+        for &(start, end) in &drags {
+            let mut zoom = ZoomState::new(&settings);
+            zoom.finish_drag(start, end, 1.0, &settings);
+            assert!(
+                zoom == expected,
+                "dragging from {:?} to {:?} did not zoom in on the box",
+                start,
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn tiny_drags_are_clicks() {
+        let settings = AppSettings::new();
+        let start = [0.3, 0.6];
+        let mut expected = ZoomState::new(&settings);
+        expected.zoom_position(start, 0.7, &settings);
+        for &(dx, dy) in &[
+            (0.0005, 0.0005),
+            (-0.0005, -0.0005),
+            (0.2, 0.0),
+            (0.0, -0.2),
+        ] {
+            let mut zoom = ZoomState::new(&settings);
+            zoom.finish_drag(start, [start[0] + dx, start[1] + dy], 0.7, &settings);
+            assert!(
+                zoom == expected,
+                "a drag by {} x {} was not taken as a click",
+                dx,
+                dy
+            );
+        }
+    }
+
+    #[test]
+    fn fine_pan_moves_by_one_pixel() {
+        let settings = AppSettings::new();
+        let start = ZoomState::new(&settings);
+        let mut zoom = start.clone();
+        zoom.pan_pixels([3.0, -2.0], &settings);
+        let pixel = start.get_scale().to_f64() / settings.resolution[1] as f64;
+        let dx = Float::with_val(64, zoom.get_x() - start.get_x()).to_f64();
+        let dy = Float::with_val(64, zoom.get_y() - start.get_y()).to_f64();
+        assert!(
+            (dx / pixel - 3.0).abs() < 1e-9,
+            "moved {} pixels along x",
+            dx / pixel
+        );
+        assert!(
+            (dy / pixel + 2.0).abs() < 1e-9,
+            "moved {} pixels along y",
+            dy / pixel
+        );
+    }
+
+    #[test]
+    fn coordinates_must_be_finite() {
+        let settings = AppSettings::new();
+        let mut zoom = ZoomState::new(&settings);
+        let start = zoom.clone();
+        assert_eq!(
+            zoom.set_coordinates(["nan", "inf", "-inf"], &settings),
+            [true, true, true]
+        );
+        assert!(zoom == start, "an invalid location moved the view");
+        assert_eq!(
+            zoom.set_coordinates(["-1", "0.25", "0.5"], &settings),
+            [false, false, false]
+        );
+        assert!(zoom != start);
+    }
+
+    #[test]
+    fn manual_apply_defers_only_settings_edits() {
+        let mut settings = AppSettings::new();
+        let mut state = AppState::new(&settings);
+        state.manual_apply = true;
+        // the first frame in manual mode makes the staged copy
+        state.build_staged(&mut settings, |_, _| {});
+        let shown = |state: &mut AppState| {
+            state.compute_valid = true;
+            state.set_valid = true;
+        };
+
+        shown(&mut state);
+        state.build_staged(&mut settings, |state, staged| {
+            staged.iterations *= 2;
+            state.compute_valid = false;
+        });
+        assert!(
+            state.compute_valid,
+            "a staged edit was computed before Render"
+        );
+        assert_ne!(
+            settings.iterations,
+            state.staged_settings.as_ref().unwrap().iterations
+        );
+
+        state.build_staged(&mut settings, |state, staged| {
+            state.move_view(ZoomState::at("-1", "0", "0.5", staged));
+        });
+        assert!(!state.compute_valid, "Reset or Go to did not move the view");
+
+        shown(&mut state);
+        state.build_staged(&mut settings, |state, _| {
+            state.show_loaded_set(ComputedSet::empty(8, 8));
+        });
+        assert!(!state.set_valid, "the loaded set is not shown");
+        assert!(state.compute_valid);
+        assert_eq!(state.computed_set.get_size(), (8, 8));
+
+        shown(&mut state);
+        state.apply_staged = true;
+        state.build_staged(&mut settings, |_, _| {});
+        assert!(!state.compute_valid);
+        assert_eq!(
+            settings.iterations,
+            state.staged_settings.as_ref().unwrap().iterations
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use num_derive::{FromPrimitive, ToPrimitive};
+use palette::LinSrgb;
+
+use crate::mandelbrot::{
+    bounded::{Bound, Orbit},
+    compute::ComputedSet,
+};
+use crate::ui::gradient::ColorGradient;
+
+#[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive)]
+pub enum ColorMode {
+    Iteration,
+    Potential,
+    Stripe,
+    /// Hue by the quadrant the orbit escaped towards, brightness by the escape count.
+    Quadrant,
+    /// Line drawing of where the escape count changes steeply, on a flat background.
+    Edges,
+    /// Grayscale relief of the estimated distance to the set, on a logarithmic scale.
+    Distance,
+}
+
+impl ColorMode {
+    pub const LIST: [Self; 6] = [
+        Self::Iteration,
+        Self::Potential,
+        Self::Stripe,
+        Self::Quadrant,
+        Self::Edges,
+        Self::Distance,
+    ];
+}
+
+/// Mapping of the normalized escape count in 0..1 onto the palette.
+#[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive)]
+pub enum TransferCurve {
+    Linear,
+    Log,
+    Sqrt,
+    Power,
+}
+
+impl TransferCurve {
+    pub const LIST: [Self; 4] = [Self::Linear, Self::Log, Self::Sqrt, Self::Power];
+
+    /// Applies the curve to `x` in 0..1, `exponent` is only used by `Power`.
+    pub fn apply(self, x: f64, exponent: f64) -> f64 {
+        // how strongly the log curve stretches the low end
+        const LOG_SCALE: f64 = 100.0;
+        match self {
+            TransferCurve::Linear => x,
+            TransferCurve::Log => (x * LOG_SCALE).ln_1p() / LOG_SCALE.ln_1p(),
+            TransferCurve::Sqrt => x.sqrt(),
+            TransferCurve::Power => x.powf(exponent),
+        }
+    }
+}
+
+/// Escape count of an orbit that escaped `|z| >= bailout` after `n` iterations ending at `z`,
+/// made continuous by -log2 of the potential `ln|z| / ln(bailout)`. Every iteration squares
+/// `|z|` and so halves that ratio, which makes the count continuous where `n` steps up.
+pub fn smooth_iteration(n: u64, z: [f64; 2], bailout: f64) -> f64 {
+    let log_z = 0.5 * (z[0] * z[0] + z[1] * z[1]).ln();
+    n as f64 + 1.0 - (log_z / bailout.ln()).log2()
+}
+
+#[derive(Clone)]
+pub struct ColorSettings {
+    pub mode: ColorMode,
+    pub transfer: TransferCurve,
+    /// Exponent of the `Power` transfer curve.
+    pub transfer_exponent: f32,
+    pub palette_offset: f32,
+    /// Number of times the palette cycles per cycle of the escape count.
+    pub density: f32,
+    pub gamma: f32,
+    pub interior_color: [f32; 3],
+    /// Colors interior pixels with a detected cycle by their period instead of `interior_color`.
+    pub color_period: bool,
+    /// Number of levels the normalized iteration is snapped to, 0 for continuous.
+    pub color_steps: u32,
+    /// Cycled through once per 360 iterations instead of the hue wheel when set.
+    pub gradient: Option<ColorGradient>,
+    /// Saturation and value of the hue wheel, whose hue is rotated by `palette_offset`.
+    pub saturation: f32,
+    pub value: f32,
+    /// Interpolates `gradient` in Lch instead of linear RGB.
+    pub lch: bool,
+    /// Draws `band_color` lines where the escape count crosses a multiple of `band_interval`.
+    pub bands: bool,
+    pub band_interval: u32,
+    pub band_color: [f32; 3],
+    /// Colors the points stored by a coordinate debug compute instead, red rising along the
+    /// real axis and green along the imaginary axis of the view.
+    pub coordinates: bool,
+    /// Gradient of the escape count above which the `Edges` mode draws a line.
+    pub edge_threshold: f32,
+    pub edge_color: [f32; 3],
+    pub edge_background: [f32; 3],
+}
+
+impl ColorSettings {
+    pub fn new() -> ColorSettings {
+        ColorSettings {
+            mode: ColorMode::Iteration,
+            transfer: TransferCurve::Linear,
+            transfer_exponent: 2.0,
+            palette_offset: 0.0,
+            density: 1.0,
+            gamma: 1.0,
+            interior_color: [0.0, 0.0, 0.0],
+            color_period: false,
+            color_steps: 0,
+            gradient: None,
+            saturation: 1.0,
+            value: 1.0,
+            lch: false,
+            bands: false,
+            band_interval: 10,
+            band_color: [1.0, 1.0, 1.0],
+            coordinates: false,
+            edge_threshold: 4.0,
+            edge_color: [0.0, 0.0, 0.0],
+            edge_background: [1.0, 1.0, 1.0],
+        }
+    }
+
+    /// Stripe average of an escaped orbit, blended between the averages with and without
+    /// its last iteration by the fractional escape count so the steps between counts vanish.
+    fn stripe_average(n: u64, orbit: &Orbit, bailout: f64) -> f64 {
+        let t = smooth_iteration(n, orbit.z, bailout).fract();
+        orbit.stripe_previous + (orbit.stripe - orbit.stripe_previous) * t
+    }
+
+    /// Color of a pixel in a set computed with `limit` iterations and escape radius `bailout`.
+    pub fn color(&self, bound: &Bound, limit: u64, bailout: f64) -> [f32; 4] {
+        match bound {
+            Bound::Bounded(orbit) if self.color_period && orbit.period > 0 => {
+                // spread periods around the wheel by the golden angle so neighbours differ
+                let c = LinSrgb::from(palette::Hsv::new(
+                    palette::RgbHue::from_degrees(orbit.period as f32 * 137.5),
+                    0.6,
+                    0.6,
+                ));
+                [c.red, c.green, c.blue, 1.0]
+            }
+            Bound::Bounded(_) => {
+                let [r, g, b] = self.interior_color;
+                [r, g, b, 1.0]
+            }
+            Bound::Unbounded(n, orbit) => {
+                let limit = limit.max(1) as f64;
+                let (position, range) = match self.mode {
+                    ColorMode::Iteration
+                    | ColorMode::Quadrant
+                    | ColorMode::Edges
+                    | ColorMode::Distance => (*n as f64, limit),
+                    ColorMode::Potential => (smooth_iteration(*n, orbit.z, bailout), limit),
+                    // the average lies in 0..1, spread it over one turn of the palette
+                    ColorMode::Stripe => (Self::stripe_average(*n, orbit, bailout) * 360.0, 360.0),
+                };
+                let normalized = self
+                    .transfer
+                    .apply((position / range).max(0.0), self.transfer_exponent as f64)
+                    .powf(1.0 / self.gamma as f64);
+                let normalized = match self.color_steps {
+                    0 => normalized,
+                    steps => (normalized * steps as f64).floor() / steps as f64,
+                };
+                let position = normalized * range * self.density as f64;
+                let c = match (self.mode, &self.gradient) {
+                    (ColorMode::Quadrant, _) => {
+                        let quadrant = match (orbit.z[0] >= 0.0, orbit.z[1] >= 0.0) {
+                            (true, true) => 0.0,
+                            (false, true) => 1.0,
+                            (false, false) => 2.0,
+                            (true, false) => 3.0,
+                        };
+                        LinSrgb::from(palette::Hsv::new(
+                            palette::RgbHue::from_degrees(
+                                quadrant * 90.0 + self.palette_offset * 360.0,
+                            ),
+                            1.0,
+                            normalized as f32,
+                        ))
+                    }
+                    (_, Some(gradient)) => gradient.get(
+                        (position as f32 / 360.0 + self.palette_offset).fract(),
+                        self.lch,
+                    ),
+                    (_, None) => LinSrgb::from(palette::Hsv::new(
+                        palette::RgbHue::from_degrees(
+                            position as f32 + self.palette_offset * 360.0,
+                        ),
+                        self.saturation,
+                        self.value,
+                    )),
+                };
+                [c.red, c.green, c.blue, 1.0]
+            }
+        }
+    }
+
+    /// Colors of `steps` escape counts spread evenly over 0..limit, for a legend of the palette.
+    pub fn legend(&self, limit: u64, bailout: f64, steps: usize) -> Vec<[f32; 4]> {
+        // escaping right at the bailout makes the smooth count exactly one more than the count
+        let orbit = Orbit {
+            z: [bailout, 0.0],
+            ..Orbit::default()
+        };
+        (0..steps as u64)
+            .map(|i| {
+                let n = limit * i / steps.max(1) as u64;
+                self.color(&Bound::Unbounded(n, orbit), limit, bailout)
+            })
+            .collect()
+    }
+
+    /// Colors every pixel of the set as RGBA, row by row, or `None` for an empty set.
+    pub fn colorize(&self, set: &ComputedSet) -> Option<Vec<f32>> {
+        set.iter().map(|data| {
+            if self.coordinates {
+                return Self::color_coordinates(data.as_slice());
+            }
+            if let ColorMode::Edges = self.mode {
+                return self.color_edges(set);
+            }
+            if let ColorMode::Distance = self.mode {
+                return self.color_distance(data.as_slice());
+            }
+            let mut pixels = Vec::with_capacity(data.len() * 4);
+            for bound in data {
+                pixels.extend_from_slice(&self.color(bound, set.get_limit(), set.get_bailout()));
+            }
+            if self.bands {
+                self.draw_bands(set, &mut pixels);
+            }
+            pixels
+        })
+    }
+
+    /// Colors the stored points of `data` by their position within all of them.
+    fn color_coordinates(data: &[Bound]) -> Vec<f32> {
+        let point = |bound: &Bound| match bound {
+            Bound::Bounded(orbit) | Bound::Unbounded(_, orbit) => orbit.z,
+        };
+        let mut min = [f64::INFINITY; 2];
+        let mut max = [f64::NEG_INFINITY; 2];
+        for z in data.iter().map(point) {
+            min = [min[0].min(z[0]), min[1].min(z[1])];
+            max = [max[0].max(z[0]), max[1].max(z[1])];
+        }
+        let mut pixels = Vec::with_capacity(data.len() * 4);
+        for z in data.iter().map(point) {
+            let t = |i: usize| ((z[i] - min[i]) / (max[i] - min[i]).max(f64::MIN_POSITIVE)) as f32;
+            pixels.extend_from_slice(&[t(0), t(1), 0.0, 1.0]);
+        }
+        pixels
+    }
+
+    /// Grays by the logarithm of the estimated distance to the set, from black at the closest
+    /// pixel to white at the farthest. Bounded pixels and those without a derivative are black.
+    fn color_distance(&self, data: &[Bound]) -> Vec<f32> {
+        let log_distance = |bound: &Bound| match bound {
+            Bound::Unbounded(_, orbit) => orbit.distance().filter(|&d| d > 0.0).map(f64::ln),
+            Bound::Bounded(_) => None,
+        };
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for d in data.iter().filter_map(log_distance) {
+            min = min.min(d);
+            max = max.max(d);
+        }
+        let mut pixels = Vec::with_capacity(data.len() * 4);
+        for bound in data {
+            let t = match log_distance(bound) {
+                Some(d) => ((d - min) / (max - min).max(f64::MIN_POSITIVE))
+                    .powf(1.0 / self.gamma as f64) as f32,
+                None => 0.0,
+            };
+            pixels.extend_from_slice(&[t, t, t, 1.0]);
+        }
+        pixels
+    }
+
+    /// Draws the pixels where the Sobel gradient of the escape count exceeds `edge_threshold`,
+    /// counting bounded pixels as escaping at the limit.
+    fn color_edges(&self, set: &ComputedSet) -> Vec<f32> {
+        let (width, height) = set.get_size();
+        let count = |x, y| match set.get(x, y) {
+            Some(Bound::Unbounded(n, _)) => n as f32,
+            _ => set.get_limit() as f32,
+        };
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                // clamp the 3x3 neighbourhood to the image
+                let xs = [x.saturating_sub(1), x, (x + 1).min(width - 1)];
+                let ys = [y.saturating_sub(1), y, (y + 1).min(height - 1)];
+                let c = |i: usize, j: usize| count(xs[i], ys[j]);
+                let gx = c(2, 0) + 2.0 * c(2, 1) + c(2, 2) - c(0, 0) - 2.0 * c(0, 1) - c(0, 2);
+                let gy = c(0, 2) + 2.0 * c(1, 2) + c(2, 2) - c(0, 0) - 2.0 * c(1, 0) - c(2, 0);
+                let [r, g, b] = if (gx * gx + gy * gy).sqrt() > self.edge_threshold {
+                    self.edge_color
+                } else {
+                    self.edge_background
+                };
+                pixels.extend_from_slice(&[r, g, b, 1.0]);
+            }
+        }
+        pixels
+    }
+
+    /// Overwrites the pixels whose escape band differs from their right or upper neighbour.
+    fn draw_bands(&self, set: &ComputedSet, pixels: &mut [f32]) {
+        let interval = self.band_interval.max(1) as u64;
+        let band = |x, y| match set.get(x, y) {
+            Some(Bound::Unbounded(n, _)) => Some(n / interval),
+            _ => None,
+        };
+        let (width, height) = set.get_size();
+        let [r, g, b] = self.band_color;
+        for y in 0..height {
+            for x in 0..width {
+                let here = band(x, y);
+                if here.is_none() {
+                    continue;
+                }
+                let right = if x + 1 < width { band(x + 1, y) } else { here };
+                let up = if y + 1 < height { band(x, y + 1) } else { here };
+                if right != here || up != here {
+                    let i = (y * width + x) as usize * 4;
+                    pixels[i..i + 4].copy_from_slice(&[r, g, b, 1.0]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mandelbrot::bounded::{BoundsChecker, BoundsSettings, Orbit};
+    use rug::{Complex, Float};
+
+    fn escape(c: f64, settings: &BoundsSettings) -> (u64, [f64; 2]) {
+        let mut out = [Bound::Bounded(Orbit::default())];
+        <f64 as BoundsChecker<f64>>::check_bounded(&[c], &[0.0], settings, &mut out);
+        match out[0] {
+            Bound::Unbounded(n, orbit) => (n, orbit.z),
+            Bound::Bounded(_) => panic!("{} did not escape", c),
+        }
+    }
+
+    #[test]
+    fn smooth_iteration_is_continuous_across_escape_counts() {
+        for &bailout in &[16.0, 256.0] {
+            let settings = BoundsSettings::new(1000, 53).with_bailout(bailout);
+            let mut steps = 0;
+            let mut previous = escape(0.3, &settings);
+            for i in 1..=20_000 {
+                let current = escape(0.3 + i as f64 * 1e-4, &settings);
+                if current.0 != previous.0 {
+                    steps += 1;
+                    let before = smooth_iteration(previous.0, previous.1, bailout);
+                    let after = smooth_iteration(current.0, current.1, bailout);
+                    assert!(
+                        (before - after).abs() < 0.05,
+                        "seam of {} at bailout {} going from {} to {} iterations",
+                        before - after,
+                        bailout,
+                        previous.0,
+                        current.0
+                    );
+                }
+                previous = current;
+            }
+            assert!(steps > 2, "the samples should cross several escape counts");
+        }
+    }
+
+    #[test]
+    fn smooth_iteration_matches_between_f64_and_precision() {
+        let settings = BoundsSettings::new(1000, 128).with_bailout(256.0);
+        for i in 0..50 {
+            let c = 0.26 + i as f64 * 0.01;
+            let (n, z) = escape(c, &settings);
+            let mut out = [Bound::Bounded(Orbit::default())];
+            <Complex as BoundsChecker<Float>>::check_bounded(
+                &[Float::with_val(128, c)],
+                &[Float::with_val(128, 0.0)],
+                &settings,
+                &mut out,
+            );
+            let (hp_n, hp_z) = match out[0] {
+                Bound::Unbounded(n, orbit) => (n, orbit.z),
+                Bound::Bounded(_) => panic!("{} did not escape at precision", c),
+            };
+            assert_eq!(n, hp_n);
+            let smooth = smooth_iteration(n, z, 256.0);
+            let hp_smooth = smooth_iteration(hp_n, hp_z, 256.0);
+            assert!(
+                (smooth - hp_smooth).abs() < 1e-6,
+                "{} against {} at {}",
+                smooth,
+                hp_smooth,
+                c
+            );
+        }
+    }
+}
@@ -1,6 +0,0 @@
-#[derive(Clone)]
-pub enum ComputeEvent {
-    Start,
-    End,
-    Progress((u32, u32)),
-}
@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use palette::{Gradient, Lch, LinSrgb, Srgb};
+
+/// A gradient through color stops, interpolated either in linear RGB or in Lch.
+#[derive(Clone)]
+pub struct ColorGradient {
+    rgb: Gradient<LinSrgb>,
+    lch: Gradient<Lch>,
+    stops: usize,
+}
+
+impl ColorGradient {
+    pub fn new(stops: Vec<(f32, LinSrgb)>) -> ColorGradient {
+        let count = stops.len();
+        let lch = stops
+            .iter()
+            .map(|&(position, color)| (position, Lch::from(color)))
+            .collect();
+        ColorGradient {
+            rgb: Gradient::with_domain(stops),
+            lch: Gradient::with_domain(lch),
+            stops: count,
+        }
+    }
+
+    /// Number of color stops the gradient was made from.
+    pub fn stops(&self) -> usize {
+        self.stops
+    }
+
+    /// Color at `t`, blending the stops in Lch when `lch` is set, which keeps the midtones
+    /// between different hues from turning gray.
+    pub fn get(&self, t: f32, lch: bool) -> LinSrgb {
+        if lch {
+            LinSrgb::from(self.lch.get(t))
+        } else {
+            self.rgb.get(t)
+        }
+    }
+}
+
+/// Named gradients that ship with the explorer, the default hue wheel not included.
+pub fn builtin_gradients() -> Vec<(&'static str, ColorGradient)> {
+    let gradient = |stops: &[(f32, [f32; 3])]| {
+        ColorGradient::new(
+            stops
+                .iter()
+                .map(|&(position, [r, g, b])| (position, LinSrgb::new(r, g, b)))
+                .collect(),
+        )
+    };
+    vec![
+        (
+            "classic",
+            gradient(&[
+                (0.0, [0.0, 0.03, 0.39]),
+                (0.16, [0.13, 0.42, 0.8]),
+                (0.42, [0.93, 1.0, 1.0]),
+                (0.64, [1.0, 0.67, 0.0]),
+                (0.86, [0.0, 0.01, 0.0]),
+                (1.0, [0.0, 0.03, 0.39]),
+            ]),
+        ),
+        (
+            "fire",
+            gradient(&[
+                (0.0, [0.0, 0.0, 0.0]),
+                (0.3, [0.8, 0.0, 0.0]),
+                (0.6, [1.0, 0.6, 0.0]),
+                (0.8, [1.0, 1.0, 0.6]),
+                (1.0, [0.0, 0.0, 0.0]),
+            ]),
+        ),
+        (
+            "ocean",
+            gradient(&[
+                (0.0, [0.0, 0.05, 0.15]),
+                (0.4, [0.0, 0.4, 0.6]),
+                (0.7, [0.6, 0.9, 0.9]),
+                (1.0, [0.0, 0.05, 0.15]),
+            ]),
+        ),
+        (
+            "grayscale",
+            gradient(&[
+                (0.0, [0.0, 0.0, 0.0]),
+                (0.5, [1.0, 1.0, 1.0]),
+                (1.0, [0.0, 0.0, 0.0]),
+            ]),
+        ),
+    ]
+}
+
+/// Most stops taken from an image, more would only slow down the lookups.
+const IMAGE_STOPS: u32 = 256;
+
+/// Loads a gradient from a GIMP `.ggr` file, from the middle row of a PNG, JPEG or BMP image,
+/// or otherwise from a text file of `position r g b` lines with all values in 0..1 and `#`
+/// starting a comment.
+pub fn load_gradient(path: &Path) -> Result<ColorGradient, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    if let Some("png") | Some("jpg") | Some("jpeg") | Some("bmp") = extension.as_deref() {
+        return gradient_from_image(path);
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let stops = if text.starts_with("GIMP Gradient") {
+        parse_ggr(&text)
+    } else {
+        parse_stops(&text)
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if stops.len() < 2 {
+        return Err(format!(
+            "{}: a gradient needs at least two stops",
+            path.display()
+        ));
+    }
+    Ok(ColorGradient::new(stops))
+}
+
+/// Samples the middle row of an image from left to right.
+fn gradient_from_image(path: &Path) -> Result<ColorGradient, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+        .to_rgb8();
+    let (width, height) = image.dimensions();
+    if width < 2 {
+        return Err(format!(
+            "{}: an image palette needs to be at least two pixels wide",
+            path.display()
+        ));
+    }
+    let count = width.min(IMAGE_STOPS);
+    let stops = (0..count)
+        .map(|i| {
+            let x = i * (width - 1) / (count - 1);
+            let [r, g, b] = image.get_pixel(x, height / 2).0;
+            let color = Srgb::new(r, g, b).into_format::<f32>().into_linear();
+            (i as f32 / (count - 1) as f32, color)
+        })
+        .collect();
+    Ok(ColorGradient::new(stops))
+}
+
+fn parse_numbers(line: &str, line_number: usize, count: usize) -> Result<Vec<f32>, String> {
+    let numbers = line
+        .split_whitespace()
+        .take(count)
+        .map(|value| {
+            value
+                .parse::<f32>()
+                .map_err(|e| format!("line {}: invalid number '{}': {}", line_number, value, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.len() < count {
+        return Err(format!(
+            "line {}: expected {} numbers, got {}",
+            line_number,
+            count,
+            numbers.len()
+        ));
+    }
+    Ok(numbers)
+}
+
+fn parse_stops(text: &str) -> Result<Vec<(f32, LinSrgb)>, String> {
+    let mut stops: Vec<(f32, LinSrgb)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.splitn(2, '#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let n = parse_numbers(line, index + 1, 4)?;
+        if stops.last().map_or(false, |(position, _)| n[0] < *position) {
+            return Err(format!("line {}: stop positions must increase", index + 1));
+        }
+        stops.push((n[0], LinSrgb::new(n[1], n[2], n[3])));
+    }
+    Ok(stops)
+}
+
+/// Uses the end colors of every segment, the midpoints and blending modes are ignored.
+fn parse_ggr(text: &str) -> Result<Vec<(f32, LinSrgb)>, String> {
+    let mut lines = text.lines().enumerate().skip(1);
+    let mut header = lines.next();
+    if let Some((_, line)) = header {
+        if line.starts_with("Name:") {
+            header = lines.next();
+        }
+    }
+    let count: usize = match header {
+        Some((index, line)) => line
+            .trim()
+            .parse()
+            .map_err(|e| format!("line {}: invalid segment count: {}", index + 1, e))?,
+        None => return Err(String::from("missing segment count")),
+    };
+    // the count comes from the file, so it only bounds the lines read
+    let mut stops = Vec::new();
+    for (index, line) in lines.take(count) {
+        // left middle right, then the left and right colors as r g b a
+        let n = parse_numbers(line, index + 1, 11)?;
+        stops.push((n[0], LinSrgb::new(n[3], n[4], n[5])));
+        stops.push((n[2], LinSrgb::new(n[7], n[8], n[9])));
+    }
+    if stops.len() / 2 < count {
+        return Err(format!(
+            "expected {} segments, got {}",
+            count,
+            stops.len() / 2
+        ));
+    }
+    Ok(stops)
+}
@@ -1,3 +1,4 @@
 pub mod app;
-pub mod events;
+pub mod color;
+pub mod gradient;
 pub mod render;
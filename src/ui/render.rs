@@ -1,13 +1,23 @@
 use glium::{
     backend::Facade,
+    draw_parameters::{Blend, DrawParameters, Smooth},
     index::PrimitiveType,
     texture::{RawImage2d, Texture2d},
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter},
     Surface,
 };
 
-use crate::mandelbrot::{bounded::Bound, compute::ComputedSet};
+use rug::Float;
 
-use crate::ui::app::AppState;
+use crate::mandelbrot::{
+    bounded::{BoundsSettings, Fractal},
+    compute::{Compute, ComputeEngine, ComputeSettings, ComputedSet},
+};
+
+use crate::ui::{
+    app::{AppSettings, AppState},
+    color::ColorSettings,
+};
 
 #[derive(Copy, Clone)]
 struct Vertex {
@@ -16,71 +26,124 @@ struct Vertex {
 }
 implement_vertex!(Vertex, position, tex_coords);
 
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Resolution, center and height of the area shown in the minimap.
+const MINIMAP_SIZE: [u32; 2] = [320, 180];
+const MINIMAP_CENTER: [f64; 2] = [-0.5, 0.0];
+const MINIMAP_SCALE: f64 = 3.0;
+/// Screen-space center and half extent of the minimap inset in the lower right corner.
+const MINIMAP_INSET: [f32; 3] = [0.73, -0.73, 0.25];
+/// Screen-space center and half extent of the selection preview in the upper right corner.
+const PREVIEW_INSET: [f32; 3] = [0.73, 0.73, 0.25];
+/// Most integer ticks drawn along each axis, none are drawn when more would be visible.
+const MAX_AXIS_TICKS: f64 = 100.0;
+/// Length of the ticks on the axes, as a fraction of the view height.
+const AXIS_TICK_SIZE: f64 = 0.01;
+
+/// Scales the fullscreen quad down along one axis so an image of `image` size keeps its
+/// aspect ratio in a `window` of a different shape.
+fn letterbox(window: (u32, u32), image: (u32, u32)) -> [[f32; 4]; 4] {
+    let window_ratio = window.0 as f32 / window.1.max(1) as f32;
+    let image_ratio = image.0 as f32 / image.1.max(1) as f32;
+    let (sx, sy) = if image_ratio > window_ratio {
+        (1.0, window_ratio / image_ratio)
+    } else {
+        (image_ratio / window_ratio, 1.0)
+    };
+    [
+        [sx, 0.0, 0.0, 0.0],
+        [0.0, sy, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+/// Transform from the screen space of the set to the window: letterboxed when the aspect ratio
+/// is locked, into the half of the window the set is drawn in while comparing.
+pub fn view_matrix(window: (u32, u32), state: &AppState, settings: &AppSettings) -> [[f32; 4]; 4] {
+    let (width, height) = window;
+    let width = if settings.compare && state.compare_set.is_some() {
+        width / 2
+    } else {
+        width
+    };
+    if settings.lock_aspect {
+        letterbox((width, height), state.computed_set.get_size())
+    } else {
+        IDENTITY
+    }
+}
+
+/// Maps a position relative to the window (0..1, y down) to the same position relative to the
+/// set drawn through `matrix`, the inverse of the letterbox.
+pub fn view_position(pos: [f64; 2], matrix: &[[f32; 4]; 4]) -> [f64; 2] {
+    [
+        (pos[0] - 0.5) / matrix[0][0] as f64 + 0.5,
+        (pos[1] - 0.5) / matrix[1][1] as f64 + 0.5,
+    ]
+}
+
+/// Squeezes `matrix` horizontally into the half of the window centered on `center`.
+fn split(mut matrix: [[f32; 4]; 4], center: f32) -> [[f32; 4]; 4] {
+    matrix[0][0] *= 0.5;
+    matrix[3][0] = center;
+    matrix
+}
+
 pub struct AppRenderer {
     computed_set_tex_cache: Option<Texture2d>,
+    compare_tex_cache: Option<Texture2d>,
+    /// Summed colors of the set and the subsamples blended into it so far.
+    accumulation: Vec<f32>,
+    accumulated: u32,
+    minimap_set: Option<(Fractal, ComputedSet)>,
+    minimap_tex_cache: Option<Texture2d>,
+    preview_tex_cache: Option<Texture2d>,
+    quad_vertices: glium::VertexBuffer<Vertex>,
+    quad_indices: glium::IndexBuffer<u16>,
+    texture_program: glium::Program,
+    line_program: glium::Program,
 }
 
 impl AppRenderer {
-    pub fn init() -> AppRenderer {
-        AppRenderer {
-            computed_set_tex_cache: None,
-        }
-    }
-
-    pub fn render<T, F>(&mut self, state: &mut AppState, target: &mut T, facade: &F)
+    pub fn init<F>(facade: &F) -> AppRenderer
     where
-        T: Surface,
         F: Facade,
     {
-        if !state.set_valid || self.computed_set_tex_cache.is_none() {
-            self.computed_set_tex_cache = Some(state.computed_set.make_texture(facade));
-            state.set_valid = true;
-        }
-        AppRenderer::render_texture(
-            self.computed_set_tex_cache.as_ref().unwrap(),
-            target,
+        let quad_vertices = glium::VertexBuffer::new(
             facade,
-        );
-        if state.dragging {
-            AppRenderer::render_select(target, facade, state);
-        }
-    }
-
-    fn render_texture<T, F>(tex: &Texture2d, target: &mut T, facade: &F)
-    where
-        T: Surface,
-        F: Facade,
-    {
-        let vertex_buffer = {
-            glium::VertexBuffer::new(
-                facade,
-                &[
-                    Vertex {
-                        position: [-1.0, -1.0],
-                        tex_coords: [0.0, 0.0],
-                    },
-                    Vertex {
-                        position: [-1.0, 1.0],
-                        tex_coords: [0.0, 1.0],
-                    },
-                    Vertex {
-                        position: [1.0, 1.0],
-                        tex_coords: [1.0, 1.0],
-                    },
-                    Vertex {
-                        position: [1.0, -1.0],
-                        tex_coords: [1.0, 0.0],
-                    },
-                ],
-            )
-            .unwrap()
-        };
+            &[
+                Vertex {
+                    position: [-1.0, -1.0],
+                    tex_coords: [0.0, 0.0],
+                },
+                Vertex {
+                    position: [-1.0, 1.0],
+                    tex_coords: [0.0, 1.0],
+                },
+                Vertex {
+                    position: [1.0, 1.0],
+                    tex_coords: [1.0, 1.0],
+                },
+                Vertex {
+                    position: [1.0, -1.0],
+                    tex_coords: [1.0, 0.0],
+                },
+            ],
+        )
+        .unwrap();
 
-        let index_buffer =
+        let quad_indices =
             glium::IndexBuffer::new(facade, PrimitiveType::TriangleStrip, &[1 as u16, 2, 0, 3])
                 .unwrap();
 
-        let program = program!(facade,
+        let texture_program = program!(facade,
             140 => {
                 vertex: "
                 #version 140
@@ -107,30 +170,225 @@ impl AppRenderer {
         )
         .unwrap();
 
+        let line_program = program!(facade, 140 => {
+            vertex: "
+                #version 140
+
+                uniform mat4 matrix;
+                in vec2 position;
+                in vec2 tex_coords;
+                void main() {
+                    gl_Position = matrix * vec4(position, 0.0, 1.0);
+                }
+            ",
+            fragment: "
+                #version 140
+
+                uniform vec4 line_color;
+                out vec4 color;
+                void main() {
+                    color = line_color;
+                }
+            "
+        })
+        .unwrap();
+
+        AppRenderer {
+            computed_set_tex_cache: None,
+            compare_tex_cache: None,
+            accumulation: Vec::new(),
+            accumulated: 0,
+            minimap_set: None,
+            minimap_tex_cache: None,
+            preview_tex_cache: None,
+            quad_vertices,
+            quad_indices,
+            texture_program,
+            line_program,
+        }
+    }
+
+    pub fn render<T, F>(
+        &mut self,
+        state: &mut AppState,
+        settings: &AppSettings,
+        target: &mut T,
+        facade: &F,
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        if !state.set_valid || self.computed_set_tex_cache.is_none() {
+            self.computed_set_tex_cache =
+                Some(state.computed_set.make_texture(facade, &settings.color));
+            self.minimap_tex_cache = None;
+            self.compare_tex_cache = None;
+            // samples colored differently can't be blended, start over from the set
+            self.accumulation = settings
+                .color
+                .colorize(&state.computed_set)
+                .unwrap_or_default();
+            self.accumulated = 1;
+            state.pending_samples.clear();
+            state.samples = 1;
+            state.set_valid = true;
+        }
+        if !state.pending_samples.is_empty() {
+            for sample in state.pending_samples.drain(..) {
+                if sample.get_size() != state.computed_set.get_size() {
+                    continue;
+                }
+                if let Some(colors) = settings.color.colorize(&sample) {
+                    for (sum, color) in self.accumulation.iter_mut().zip(colors) {
+                        *sum += color;
+                    }
+                    self.accumulated += 1;
+                }
+            }
+            let scale = 1.0 / self.accumulated as f32;
+            let average = self.accumulation.iter().map(|sum| sum * scale).collect();
+            self.computed_set_tex_cache = Some(
+                Texture2d::new(
+                    facade,
+                    RawImage2d::from_raw_rgba(average, state.computed_set.get_size()),
+                )
+                .unwrap(),
+            );
+        }
+        if !state.compare_valid || self.compare_tex_cache.is_none() {
+            self.compare_tex_cache = state
+                .compare_set
+                .as_ref()
+                .map(|set| set.make_texture(facade, &settings.color));
+            state.compare_valid = true;
+        }
+        let compare = match &self.compare_tex_cache {
+            Some(tex) if settings.compare => Some(tex),
+            _ => None,
+        };
+        let matrix = view_matrix(target.get_dimensions(), state, settings);
+        let tex = self.computed_set_tex_cache.as_ref().unwrap();
+        let smooth = settings.smooth_sampling;
+        match compare {
+            Some(compare) => {
+                self.render_texture(tex, target, split(matrix, -0.5), smooth);
+                self.render_texture(compare, target, split(matrix, 0.5), smooth);
+            }
+            None => self.render_texture(tex, target, matrix, smooth),
+        }
+        if settings.show_axes {
+            self.render_axes(target, facade, state, settings, matrix);
+        }
+        if settings.show_crosshair {
+            self.render_crosshair(target, facade, state, matrix);
+        }
+        if settings.show_minimap {
+            self.render_minimap(target, facade, state, settings);
+        }
+        if !state.orbit.is_empty() {
+            self.render_orbit(target, facade, state, settings, matrix);
+        }
+        if state.dragging {
+            self.render_select(target, facade, state, settings, matrix);
+            if state.preview.is_some() {
+                self.render_preview(target, facade, state, settings);
+            }
+        }
+    }
+
+    /// Draws `tex` on the quad transformed by `matrix`, interpolating between its pixels when
+    /// `smooth` is set and showing each as a sharp block otherwise.
+    fn render_texture<T>(
+        &self,
+        tex: &Texture2d,
+        target: &mut T,
+        matrix: [[f32; 4]; 4],
+        smooth: bool,
+    ) where
+        T: Surface,
+    {
+        let (magnify, minify) = if smooth {
+            (MagnifySamplerFilter::Linear, MinifySamplerFilter::Linear)
+        } else {
+            (MagnifySamplerFilter::Nearest, MinifySamplerFilter::Nearest)
+        };
         let uniforms = uniform! {
-            matrix: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0f32]
-            ],
-            tex: tex
+            matrix: matrix,
+            tex: tex.sampled().magnify_filter(magnify).minify_filter(minify)
         };
         target
             .draw(
-                &vertex_buffer,
-                &index_buffer,
-                &program,
+                &self.quad_vertices,
+                &self.quad_indices,
+                &self.texture_program,
                 &uniforms,
                 &Default::default(),
             )
             .unwrap();
     }
 
-    fn render_select<T, F>(target: &mut T, facade: &F, state: &AppState)
+    fn render_inset<T, F>(&self, target: &mut T, facade: &F, tex: &Texture2d, inset: [f32; 3])
     where
         T: Surface,
         F: Facade,
+    {
+        let [cx, cy, size] = inset;
+        let matrix = [
+            [size, 0.0, 0.0, 0.0],
+            [0.0, size, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [cx, cy, 0.0, 1.0],
+        ];
+        self.render_texture(tex, target, matrix, true);
+        self.render_lines(
+            target,
+            facade,
+            &[
+                [cx - size, cy - size],
+                [cx + size, cy - size],
+                [cx + size, cy + size],
+                [cx - size, cy + size],
+            ],
+            &[0, 1, 2, 3],
+            PrimitiveType::LineLoop,
+            [1.0, 1.0, 1.0, 1.0],
+            IDENTITY,
+        );
+    }
+
+    fn render_preview<T, F>(
+        &mut self,
+        target: &mut T,
+        facade: &F,
+        state: &mut AppState,
+        settings: &AppSettings,
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        if !state.preview_valid || self.preview_tex_cache.is_none() {
+            let preview = state.preview.as_ref().unwrap();
+            self.preview_tex_cache = Some(preview.make_texture(facade, &settings.color));
+            state.preview_valid = true;
+        }
+        self.render_inset(
+            target,
+            facade,
+            self.preview_tex_cache.as_ref().unwrap(),
+            PREVIEW_INSET,
+        );
+    }
+
+    fn render_select<T, F>(
+        &self,
+        target: &mut T,
+        facade: &F,
+        state: &AppState,
+        settings: &AppSettings,
+        matrix: [[f32; 4]; 4],
+    ) where
+        T: Surface,
+        F: Facade,
     {
         let (x1, y1, x2, y2) = (
             2.0 * state.mouse_start[0] as f32 - 1.0,
@@ -138,71 +396,288 @@ impl AppRenderer {
             2.0 * state.mouse_end[0] as f32 - 1.0,
             -2.0 * state.mouse_end[1] as f32 + 1.0,
         );
+        let [r, g, b] = settings.selection_color;
+
+        if settings.selection_dim > 0.0 {
+            let (left, right) = (x1.min(x2), x1.max(x2));
+            let (bottom, top) = (y1.min(y2), y1.max(y2));
+            // the strips left and right of the selection and the parts above and below it
+            let rects = [
+                [-1.0, -1.0, left, 1.0],
+                [right, -1.0, 1.0, 1.0],
+                [left, -1.0, right, bottom],
+                [left, top, right, 1.0],
+            ];
+            let mut points = Vec::with_capacity(16);
+            let mut indices = Vec::with_capacity(24);
+            for [xa, ya, xb, yb] in rects.iter().copied() {
+                let i = points.len() as u16;
+                points.extend_from_slice(&[[xa, ya], [xb, ya], [xb, yb], [xa, yb]]);
+                indices.extend_from_slice(&[i, i + 1, i + 2, i, i + 2, i + 3]);
+            }
+            self.render_lines(
+                target,
+                facade,
+                &points,
+                &indices,
+                PrimitiveType::TrianglesList,
+                [0.0, 0.0, 0.0, settings.selection_dim],
+                matrix,
+            );
+        }
+        self.render_lines(
+            target,
+            facade,
+            &[[x1, y1], [x2, y1], [x2, y2], [x1, y2]],
+            &[0, 1, 2, 3],
+            PrimitiveType::LineLoop,
+            [r, g, b, 1.0],
+            matrix,
+        );
+    }
+
+    fn render_orbit<T, F>(
+        &self,
+        target: &mut T,
+        facade: &F,
+        state: &AppState,
+        settings: &AppSettings,
+        matrix: [[f32; 4]; 4],
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        let points: Vec<[f32; 2]> = state
+            .orbit
+            .iter()
+            .map(|&z| state.zoomstate.screen_position(z, settings))
+            .collect();
+        let indices: Vec<u16> = (0..points.len() as u16).collect();
+
+        self.render_lines(
+            target,
+            facade,
+            &points,
+            &indices,
+            PrimitiveType::LineStrip,
+            [1.0, 1.0, 0.0, 1.0],
+            matrix,
+        );
+    }
+
+    /// Draws the real and imaginary axes with ticks at the integers along them.
+    fn render_axes<T, F>(
+        &self,
+        target: &mut T,
+        facade: &F,
+        state: &AppState,
+        settings: &AppSettings,
+        matrix: [[f32; 4]; 4],
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        let zoom = &state.zoomstate;
+        let [min, max] = zoom.view_rect(settings);
+        // reaches past the corners of the view however it is rotated
+        let reach = (max[0] - min[0]).hypot(max[1] - min[1]);
+        let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let mut points = vec![
+            [center[0] - reach, 0.0],
+            [center[0] + reach, 0.0],
+            [0.0, center[1] - reach],
+            [0.0, center[1] + reach],
+        ];
+        let tick = (max[1] - min[1]) * AXIS_TICK_SIZE;
+        if 2.0 * reach <= MAX_AXIS_TICKS {
+            let integers = |c: f64| (c - reach).ceil() as i64..=(c + reach).floor() as i64;
+            for k in integers(center[0]) {
+                points.extend_from_slice(&[[k as f64, -tick], [k as f64, tick]]);
+            }
+            for k in integers(center[1]) {
+                points.extend_from_slice(&[[-tick, k as f64], [tick, k as f64]]);
+            }
+        }
+        let points: Vec<[f32; 2]> = points
+            .into_iter()
+            .map(|z| zoom.screen_position(z, settings))
+            .collect();
+        let indices: Vec<u16> = (0..points.len() as u16).collect();
+        self.render_lines(
+            target,
+            facade,
+            &points,
+            &indices,
+            PrimitiveType::LinesList,
+            [1.0, 1.0, 1.0, 0.6],
+            matrix,
+        );
+    }
+
+    fn render_crosshair<T, F>(
+        &self,
+        target: &mut T,
+        facade: &F,
+        state: &AppState,
+        matrix: [[f32; 4]; 4],
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        self.render_lines(
+            target,
+            facade,
+            &[[-1.0, 0.0], [1.0, 0.0], [0.0, -1.0], [0.0, 1.0]],
+            &[0, 1, 2, 3],
+            PrimitiveType::LinesList,
+            [1.0, 1.0, 1.0, 1.0],
+            matrix,
+        );
+
+        if let Some(view) = &state.view_zoom {
+            let (width, height) = state.computed_set.get_size();
+            let [x, y] = state
+                .zoomstate
+                .screen_offset(view, width as f64 / height as f64);
+            let (dx, dy) = (0.01, 0.01 * width as f32 / height as f32);
+            self.render_lines(
+                target,
+                facade,
+                &[
+                    [x - dx, y - dy],
+                    [x + dx, y - dy],
+                    [x + dx, y + dy],
+                    [x - dx, y + dy],
+                ],
+                &[0, 1, 2, 3],
+                PrimitiveType::LineLoop,
+                [0.0, 1.0, 1.0, 1.0],
+                matrix,
+            );
+        }
+    }
+
+    /// Draws a low resolution overview of the whole fractal with the current view outlined.
+    fn render_minimap<T, F>(
+        &mut self,
+        target: &mut T,
+        facade: &F,
+        state: &AppState,
+        settings: &AppSettings,
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        // the overview only has to be computed again when the formula changes
+        if self
+            .minimap_set
+            .as_ref()
+            .map_or(true, |(fractal, _)| *fractal != settings.fractal)
+        {
+            let compute_settings = ComputeSettings::new(
+                Float::with_val(53, MINIMAP_CENTER[0]),
+                Float::with_val(53, MINIMAP_CENTER[1]),
+                Float::with_val(53, MINIMAP_SCALE),
+                MINIMAP_SIZE[0],
+                MINIMAP_SIZE[1],
+                ComputeEngine::SimdF64x4,
+                BoundsSettings::new(250, 53).with_fractal(settings.fractal),
+            );
+            let set = Compute::compute_set(None, None, &compute_settings)
+                .unwrap_or_else(|_| ComputedSet::empty(MINIMAP_SIZE[0], MINIMAP_SIZE[1]));
+            self.minimap_set = Some((settings.fractal, set));
+            self.minimap_tex_cache = None;
+        }
+        if self.minimap_tex_cache.is_none() {
+            let (_, set) = self.minimap_set.as_ref().unwrap();
+            self.minimap_tex_cache = Some(set.make_texture(facade, &settings.color));
+        }
+
+        self.render_inset(
+            target,
+            facade,
+            self.minimap_tex_cache.as_ref().unwrap(),
+            MINIMAP_INSET,
+        );
+
+        let [cx, cy, size] = MINIMAP_INSET;
 
-        let rect = glium::VertexBuffer::new(
+        let width = MINIMAP_SCALE * MINIMAP_SIZE[0] as f64 / MINIMAP_SIZE[1] as f64;
+        let to_screen = |[x, y]: [f64; 2]| {
+            let u = ((x - MINIMAP_CENTER[0]) / width * 2.0).max(-1.0).min(1.0);
+            let v = ((y - MINIMAP_CENTER[1]) / MINIMAP_SCALE * 2.0)
+                .max(-1.0)
+                .min(1.0);
+            [cx + u as f32 * size, cy + v as f32 * size]
+        };
+        let [low, high] = state.zoomstate.view_rect(settings);
+        let [x1, y1] = to_screen(low);
+        let [x2, y2] = to_screen(high);
+        // keep deep zooms visible as a small square instead of a single pixel
+        let (mx, my) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+        let (dx, dy) = (((x2 - x1) / 2.0).max(0.005), ((y2 - y1) / 2.0).max(0.005));
+        self.render_lines(
+            target,
             facade,
             &[
-                Vertex {
-                    position: [x1, y1],
-                    tex_coords: [0.0, 0.0],
-                },
-                Vertex {
-                    position: [x2, y1],
-                    tex_coords: [0.0, 0.0],
-                },
-                Vertex {
-                    position: [x2, y2],
-                    tex_coords: [0.0, 0.0],
-                },
-                Vertex {
-                    position: [x1, y2],
-                    tex_coords: [0.0, 0.0],
-                },
+                [mx - dx, my - dy],
+                [mx + dx, my - dy],
+                [mx + dx, my + dy],
+                [mx - dx, my + dy],
             ],
-        )
-        .unwrap();
+            &[0, 1, 2, 3],
+            PrimitiveType::LineLoop,
+            [1.0, 0.0, 0.0, 1.0],
+            IDENTITY,
+        );
+    }
+
+    fn render_lines<T, F>(
+        &self,
+        target: &mut T,
+        facade: &F,
+        points: &[[f32; 2]],
+        indices: &[u16],
+        primitive: PrimitiveType,
+        color: [f32; 4],
+        matrix: [[f32; 4]; 4],
+    ) where
+        T: Surface,
+        F: Facade,
+    {
+        let vertices: Vec<Vertex> = points
+            .iter()
+            .map(|&position| Vertex {
+                position,
+                tex_coords: [0.0, 0.0],
+            })
+            .collect();
+        let vertex_buffer = glium::VertexBuffer::new(facade, &vertices).unwrap();
 
-        let index_buffer =
-            glium::IndexBuffer::new(facade, PrimitiveType::LineLoop, &[0 as u16, 1, 2, 3]).unwrap();
+        let index_buffer = glium::IndexBuffer::new(facade, primitive, indices).unwrap();
 
         let uniforms = uniform! {
-            matrix: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0f32]
-            ]
+            matrix: matrix,
+            line_color: color
+        };
+        // smoothing polygons would leave seams along the diagonals of the quads
+        let smooth = match primitive {
+            PrimitiveType::TrianglesList => None,
+            _ => Some(Smooth::Nicest),
+        };
+        let parameters = DrawParameters {
+            blend: Blend::alpha_blending(),
+            smooth,
+            ..Default::default()
         };
-
-        let program = program!(facade, 140 => {
-            vertex: "
-                #version 140
-
-                uniform mat4 matrix;
-                in vec2 position;
-                in vec2 tex_coords;
-                void main() {
-                    gl_Position = matrix * vec4(position, 0.0, 1.0);
-                }
-            ",
-            fragment: "
-                #version 140
-                
-                out vec4 color;
-                void main() {
-                    color = vec4(1.0, 0.0, 0.0, 0.0);
-                }
-            "
-        })
-        .unwrap();
 
         target
             .draw(
-                &rect,
+                &vertex_buffer,
                 &index_buffer,
-                &program,
+                &self.line_program,
                 &uniforms,
-                &Default::default(),
+                &parameters,
             )
             .unwrap();
     }
@@ -212,35 +687,18 @@ pub trait MakeTexture<F>
 where
     F: Facade,
 {
-    fn make_texture(&self, facade: &F) -> Texture2d;
+    fn make_texture(&self, facade: &F, color: &ColorSettings) -> Texture2d;
 }
 
 impl<F> MakeTexture<F> for ComputedSet
 where
     F: Facade,
 {
-    fn make_texture(&self, facade: &F) -> Texture2d {
-        match self.iter() {
-            Some(data) => Texture2d::new(
-                facade,
-                RawImage2d::from_raw_rgba(
-                    data.flat_map(|bound| match bound {
-                        Bound::Bounded => vec![0.0, 0.0, 0.0, 1.0],
-                        Bound::Unbounded(n) => {
-                            let c = palette::Hsv::new(
-                                palette::RgbHue::from_degrees(*n as f32),
-                                1.0,
-                                1.0,
-                            );
-                            let c = palette::LinSrgb::from(c);
-                            vec![c.red, c.green, c.blue, 1.0]
-                        }
-                    })
-                    .collect::<Vec<f32>>(),
-                    self.get_size(),
-                ),
-            )
-            .unwrap(),
+    fn make_texture(&self, facade: &F, color: &ColorSettings) -> Texture2d {
+        match color.colorize(self) {
+            Some(data) => {
+                Texture2d::new(facade, RawImage2d::from_raw_rgba(data, self.get_size())).unwrap()
+            }
             None => Texture2d::empty(facade, self.get_size().0, self.get_size().1).unwrap(),
         }
     }